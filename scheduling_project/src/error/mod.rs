@@ -0,0 +1,126 @@
+use std::{fmt, io};
+
+/// Every error reported by the crate
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed
+    Io {
+        path: Option<String>,
+        source: io::Error,
+    },
+    /// An input couldn't be parsed, `line` starts at 1
+    Parse {
+        path: Option<String>,
+        line: Option<usize>,
+        message: String,
+    },
+    /// Inputs the simulations can't work with, e.g. duplicate names
+    Validation(String),
+    /// Out of range simulation, generator or distribution parameters
+    Configuration(String),
+}
+
+impl Error {
+    pub fn parse(line: Option<usize>, message: impl Into<String>) -> Error {
+        Error::Parse {
+            path: None,
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn configuration(message: impl Into<String>) -> Error {
+        Error::Configuration(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>) -> Error {
+        Error::Validation(message.into())
+    }
+
+    /// Records the file an I/O or parse error comes from, unless already known
+    pub fn in_file(mut self, file: &str) -> Error {
+        if let Error::Io { path, .. } | Error::Parse { path, .. } = &mut self {
+            path.get_or_insert_with(|| file.to_owned());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => match path {
+                Some(path) => write!(f, "{}: {}", path, source),
+                None => write!(f, "{}", source),
+            },
+            Error::Parse {
+                path,
+                line,
+                message,
+            } => {
+                if let Some(path) = path {
+                    write!(f, "{}: ", path)?;
+                }
+                if let Some(line) = line {
+                    write!(f, "line {}: ", line)?;
+                }
+                write!(f, "{}", message)
+            }
+            Error::Validation(message) => write!(f, "invalid input: {}", message),
+            Error::Configuration(message) => write!(f, "invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Error::Io { path: None, source }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        if error.is_io() {
+            return Error::Io {
+                path: None,
+                source: error.into(),
+            };
+        }
+        // serde_json appends the position to its messages, it is kept in `line` instead
+        let message = error.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(position) => message[..position].to_owned(),
+            None => message,
+        };
+        Error::parse(Some(error.line()).filter(|x| *x > 0), message)
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let error = serde_json::from_str::<Vec<u64>>("[1,\n2,\n\"3\"]").unwrap_err();
+        let error = Error::from(error).in_file("references.json");
+        assert!(matches!(error, Error::Parse { line: Some(3), .. }));
+        assert!(error
+            .to_string()
+            .starts_with("references.json: line 3: invalid type"));
+
+        let error = Error::from(io::Error::from(io::ErrorKind::NotFound)).in_file("a.json");
+        assert!(error.to_string().starts_with("a.json: "));
+        // The innermost path wins
+        assert!(error.in_file("b.json").to_string().starts_with("a.json: "));
+    }
+}
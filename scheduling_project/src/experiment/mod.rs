@@ -1,21 +1,63 @@
-use std::{
-    f32::INFINITY,
-    fs::File,
-    io::{Error, Write},
-};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+use std::{fs::File, io::Write};
+
+use rand::{rngs::StdRng, SeedableRng};
 
+#[cfg(target_os = "linux")]
+use crate::ground_truth::{compare_with_simulations, GroundTruthConfig};
 use crate::{
-    paging::{fifo, generate_normal_distribution_of_references, least_recently_used},
-    process::{generate_queue, save_processes_list_to_path, Process},
+    error::Error,
+    paging::{
+        belady::{search_belady_anomalies, BeladySearchConfig, BeladySearchStrategy},
+        fifo, generate_hot_set_with_scan, generate_normal_distribution_of_references,
+        generators::{
+            generate_references, MarkovChain, Mix, PhasedWorkingSet, ReferenceGenerator, Scan,
+            ZipfPopularity,
+        },
+        least_recently_used,
+        multiprocess::{
+            simulate_multiprocess_paging, FrameAllocation, InterleavingSchedule,
+            MultiprocessPagingConfig, PagingProcess, ReplacementScope,
+        },
+        page_fault_frequency::{page_fault_frequency, PageFaultFrequencyConfig},
+        programs::{
+            binary_search, blocked_matrix_multiply, column_major_traversal, linked_list_walk,
+            matrix_multiply, row_major_traversal, MemoryLayout,
+        },
+        scan_resistant::{lirs, two_queue, LirsConfig, TwoQueueConfig},
+        stack_distance::stack_distance_analysis,
+        translation::{
+            translate_addresses, AccessLatencies, AddressTranslationConfig, TlbConfig,
+            TlbReplacement,
+        },
+        working_set::{working_set, ws_clock, WorkingSetWindow},
+        write_back::{simulate_write_back, tag_writes, IoCostModel},
+        Fifo, LeastRecentlyUsed, PagingSimulator,
+    },
+    process::{
+        arrivals::{ArrivalProcess, OnOffBursts, Periodic, Poisson},
+        bursts::BurstDistribution,
+        generate_queue, generate_queue_with_arrivals, save_processes_list_to_path,
+        swf::{read_swf_from, save_swf_to_path, swf_to_processes, SwfFilter},
+        ProcessSpec,
+    },
     scheduling::{fcfs, round_robin},
+    virtual_memory::{sweep_multiprogramming_degree, VirtualMemoryConfig},
+    workload::{generate_workload, read_workload_spec_from},
 };
 
-pub fn round_robin_processing_time() {
+/// Seed of every random generator used by the experiments,
+/// recorded in every result file
+const SEED: u64 = 2024;
+
+pub fn round_robin_processing_time() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(SEED);
     let min_time = 10.0;
     let max_time = 250.0;
     let processes_count = 50;
     let burst_time_standard_deviation = 1.0;
-    let mut queues: Vec<Vec<Process>> = vec![];
+    let mut queues: Vec<Vec<ProcessSpec>> = vec![];
     let mut average_waiting_times_for_different_execution_times: Vec<f32> = vec![];
     let mut output: String = "Processing time,Mean burst time,Average waiting time\n".to_owned();
     for mean_burst_time in (10..140).step_by(30) {
@@ -23,9 +65,10 @@ pub fn round_robin_processing_time() {
             processes_count,
             mean_burst_time as f32,
             burst_time_standard_deviation,
-        );
+            &mut rng,
+        )?;
         for i in ((min_time as usize)..(max_time as usize)).step_by(20) {
-            let robin = round_robin(queue.clone(), i as f32);
+            let robin = round_robin(queue.clone(), i as f32)?;
             average_waiting_times_for_different_execution_times.push(robin.average_waiting_time);
             output += &format!(
                 "{},{},{}\n",
@@ -38,23 +81,25 @@ pub fn round_robin_processing_time() {
         }
         queues.push(queue);
     }
-    let serialized_input = serde_json::to_string(&queues).unwrap();
+    let serialized_input = serde_json::to_string(&queues)?;
     save_string_to_file(
         serialized_input,
         "test_data/round_robin_processing_time_input.csv",
-    );
-    save_string_to_file(output, "test_data/round_robin_processing_time_output.csv");
+    )?;
+    save_results_to_file(output, "test_data/round_robin_processing_time_output.csv")?;
+    Ok(())
 }
 
-pub fn fcfs_vs_round_robin() {
-    let mut queues: Vec<Vec<Process>> = vec![];
+pub fn fcfs_vs_round_robin() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut queues: Vec<Vec<ProcessSpec>> = vec![];
     let mut robin_waiting_times: Vec<f32> = vec![];
     let mut fcfs_waiting_times: Vec<f32> = vec![];
     let mut output =
         "Mean burst time,Round-robin avg waiting time,FCFS avg waiting time\n".to_owned();
     for i in (10..110).step_by(10) {
-        let queue = generate_queue(50, i as f32, 3.0);
-        let robin = round_robin(queue.clone(), (i / 2) as f32);
+        let queue = generate_queue(50, i as f32, 3.0, &mut rng)?;
+        let robin = round_robin(queue.clone(), (i / 2) as f32)?;
         let fcfs = fcfs(queue.clone());
         robin_waiting_times.push(robin.average_waiting_time);
         fcfs_waiting_times.push(fcfs.average_waiting_time);
@@ -64,49 +109,53 @@ pub fn fcfs_vs_round_robin() {
         );
         queues.push(queue);
     }
-    let serialized_input = serde_json::to_string(&queues).unwrap();
-    save_string_to_file(serialized_input, "test_data/fcfs_vs_round_robin_input.json");
-    save_string_to_file(output, "test_data/fcfs_vs_round_robin_output.csv");
+    let serialized_input = serde_json::to_string(&queues)?;
+    save_string_to_file(serialized_input, "test_data/fcfs_vs_round_robin_input.json")?;
+    save_results_to_file(output, "test_data/fcfs_vs_round_robin_output.csv")?;
+    Ok(())
 }
 
-pub fn convoy_effect() {
-    let mut asc_queue = generate_queue(50, 10.0, 3.0);
-    for i in 0..asc_queue.len() {
-        asc_queue[i].arrival_time = 0.0;
+pub fn convoy_effect() -> Result<(), Error> {
+    let mut asc_queue = generate_queue(50, 10.0, 3.0, &mut StdRng::seed_from_u64(SEED))?;
+    for process in asc_queue.iter_mut() {
+        process.arrival_time = 0.0;
     }
     asc_queue.sort_by_key(|entry| entry.burst_time as i16);
     let mut desc_queue = asc_queue.clone();
     desc_queue.reverse();
     let fcfs_asc = fcfs(asc_queue.clone());
     let fcfs_desc = fcfs(desc_queue.clone());
-    save_processes_list_to_path(&asc_queue, "test_data/convoy_ascending_input.json");
-    save_processes_list_to_path(&desc_queue, "test_data/convoy_descending_input.json");
-    fcfs_asc.to_csv("test_data/convoy_asc_output.csv");
-    fcfs_desc.to_csv("test_data/convoy_desc_output.csv");
+    save_processes_list_to_path(&asc_queue, "test_data/convoy_ascending_input.json")?;
+    save_processes_list_to_path(&desc_queue, "test_data/convoy_descending_input.json")?;
+    save_results_to_file(fcfs_asc.to_csv_string(), "test_data/convoy_asc_output.csv")?;
+    save_results_to_file(
+        fcfs_desc.to_csv_string(),
+        "test_data/convoy_desc_output.csv",
+    )?;
     let summary = format!(
         "ASC,DESC\n{},{}",
         fcfs_asc.average_waiting_time, fcfs_desc.average_waiting_time
     );
-    save_string_to_file(summary, "test_data/convoy_summary.csv");
+    save_results_to_file(summary, "test_data/convoy_summary.csv")?;
+    Ok(())
 }
 
-pub fn fifo_vs_least_recently_used() {
-    let addresses_count = 64;
+pub fn fifo_vs_least_recently_used() -> Result<(), Error> {
     let physical_memory_size = 32;
-    let mut fifo_worst_ratio: f32 = INFINITY;
+    let mut fifo_worst_ratio: f32 = f32::INFINITY;
     let mut fifo_best_ratio: f32 = 0.0;
-    let mut lru_worst_ratio = INFINITY;
+    let mut lru_worst_ratio = f32::INFINITY;
     let mut lru_best_ratio = 0.0;
     let mut fifo_ratios: Vec<f32> = vec![];
     let mut lru_ratios: Vec<f32> = vec![];
-    let mut all_references: Vec<Vec<i16>> = vec![];
+    let mut all_references: Vec<Vec<u64>> = vec![];
+    let mut rng = StdRng::seed_from_u64(SEED);
 
     for _ in 0..100 {
-        let references = generate_normal_distribution_of_references(64, 16.0, 10.0);
+        let references = generate_normal_distribution_of_references(64, 16.0, 10.0, &mut rng)?;
         all_references.push(references.clone());
-        let fifo = fifo(addresses_count, physical_memory_size, references.clone());
-        let least_recently_used =
-            least_recently_used(addresses_count, physical_memory_size, references);
+        let fifo = fifo(physical_memory_size, references.clone())?;
+        let least_recently_used = least_recently_used(physical_memory_size, references)?;
 
         lru_ratios.push(least_recently_used.hit_miss_ratio);
         fifo_ratios.push(fifo.hit_miss_ratio);
@@ -140,13 +189,780 @@ pub fn fifo_vs_least_recently_used() {
         fifo_worst_ratio,
         lru_worst_ratio
     );
-    let input = serde_json::to_string(&all_references).unwrap();
-    save_string_to_file(input, "test_data/fifo_vs_lru_input.csv");
-    save_string_to_file(output, "test_data/fifo_vs_lru_output.csv");
+    let input = serde_json::to_string(&all_references)?;
+    save_string_to_file(input, "test_data/fifo_vs_lru_input.csv")?;
+    save_results_to_file(output, "test_data/fifo_vs_lru_output.csv")?;
+    Ok(())
 }
 
-fn save_string_to_file(string: String, path: &str) -> Result<(), Error> {
-    let mut file = File::create(path).unwrap();
-    file.write_all(string.as_bytes()).unwrap();
+pub fn scan_resistance() -> Result<(), Error> {
+    let hot_set_size = 16;
+    let physical_memory_size = 24;
+    let mut all_references: Vec<Vec<u64>> = vec![];
+    let mut output = "Scan length,FIFO misses,LRU misses,2Q misses,LIRS misses\n".to_owned();
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    for scan_length in (0..=400).step_by(50) {
+        let references = generate_hot_set_with_scan(hot_set_size, 400, scan_length, &mut rng);
+        all_references.push(references.clone());
+        let fifo = fifo(physical_memory_size, references.clone())?;
+        let least_recently_used = least_recently_used(physical_memory_size, references.clone())?;
+        let two_queue = two_queue(
+            physical_memory_size,
+            references.clone(),
+            TwoQueueConfig::default(),
+        )?;
+        let lirs = lirs(
+            physical_memory_size,
+            references,
+            LirsConfig { hir_ratio: 0.25 },
+        )?;
+        output += &format!(
+            "{},{},{},{},{}\n",
+            scan_length, fifo.misses, least_recently_used.misses, two_queue.misses, lirs.misses
+        );
+    }
+
+    let input = serde_json::to_string(&all_references)?;
+    save_string_to_file(input, "test_data/scan_resistance_input.json")?;
+    save_results_to_file(output, "test_data/scan_resistance_output.csv")?;
+    Ok(())
+}
+
+/// Tracks W(t, Δ) of a reference string generated the same way as in
+/// `fifo_vs_least_recently_used`, so that it can be plotted against
+/// the physical memory size used there
+pub fn working_set_size_over_time() -> Result<(), Error> {
+    let physical_memory_size = 32;
+    let windows: Vec<usize> = vec![16, 64, 256];
+    let references = generate_normal_distribution_of_references(
+        64,
+        16.0,
+        10.0,
+        &mut StdRng::seed_from_u64(SEED),
+    )?;
+    let working_sets: Vec<_> = windows
+        .iter()
+        .map(|window| {
+            working_set(
+                references.clone(),
+                None,
+                WorkingSetWindow::References(*window),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut output = "Reference index,Physical memory size".to_owned();
+    for window in &windows {
+        output += &format!(",W(t; {})", window);
+    }
+    for i in 0..references.len() {
+        output += &format!("\n{},{}", i, physical_memory_size);
+        for results in &working_sets {
+            output += &format!(",{}", results.working_set_sizes[i]);
+        }
+    }
+
+    let mut summary = "Window,Average working set size,Peak working set size,Working set misses,WSClock misses,FIFO misses,LRU misses\n".to_owned();
+    let fifo = fifo(physical_memory_size, references.clone())?;
+    let least_recently_used = least_recently_used(physical_memory_size, references.clone())?;
+    for (window, results) in windows.iter().zip(&working_sets) {
+        let ws_clock = ws_clock(
+            physical_memory_size,
+            references.clone(),
+            None,
+            WorkingSetWindow::References(*window),
+        )?;
+        summary += &format!(
+            "{},{},{},{},{},{},{}\n",
+            window,
+            results.average_working_set_size,
+            results.peak_working_set_size,
+            results.misses,
+            ws_clock.misses,
+            fifo.misses,
+            least_recently_used.misses
+        );
+    }
+
+    let input = serde_json::to_string(&references)?;
+    save_string_to_file(input, "test_data/working_set_input.json")?;
+    save_results_to_file(output, "test_data/working_set_output.csv")?;
+    save_results_to_file(summary, "test_data/working_set_summary.csv")?;
     Ok(())
 }
+
+pub fn page_fault_frequency_allocation() -> Result<(), Error> {
+    let references = generate_normal_distribution_of_references(
+        64,
+        16.0,
+        10.0,
+        &mut StdRng::seed_from_u64(SEED),
+    )?;
+    let config = PageFaultFrequencyConfig {
+        initial_frames: 8,
+        min_frames: 4,
+        max_frames: 64,
+        measurement_window: 32,
+        lower_fault_rate: 0.1,
+        upper_fault_rate: 0.3,
+    };
+    let results = page_fault_frequency(references.clone(), config)?;
+
+    let mut output = "Reference index,Reference,Fault rate,Allocated frames".to_owned();
+    for (i, reference) in references.iter().enumerate() {
+        output += &format!(
+            "\n{},{},{},{}",
+            i, reference, results.fault_rate_history[i], results.frame_allocation_history[i]
+        );
+    }
+
+    let input = serde_json::to_string(&references)?;
+    save_string_to_file(input, "test_data/page_fault_frequency_input.json")?;
+    save_results_to_file(output, "test_data/page_fault_frequency_output.csv")?;
+    Ok(())
+}
+
+/// Least recently used miss ratio for every physical memory size
+/// at once, instead of one `least_recently_used` run per size
+pub fn least_recently_used_miss_ratio_curve() -> Result<(), Error> {
+    let references = generate_normal_distribution_of_references(
+        64,
+        16.0,
+        10.0,
+        &mut StdRng::seed_from_u64(SEED),
+    )?;
+    let results = stack_distance_analysis(&references);
+
+    let mut curve = "Physical memory size,Misses,Miss ratio".to_owned();
+    for (size, misses) in results.misses.iter().enumerate() {
+        curve += &format!("\n{},{},{}", size, misses, results.miss_ratio_curve[size]);
+    }
+    let mut histogram = format!(
+        "Reuse distance,References count\nCold,{}",
+        results.cold_misses
+    );
+    for (distance, count) in results.reuse_distance_histogram.iter().enumerate() {
+        histogram += &format!("\n{},{}", distance, count);
+    }
+
+    let input = serde_json::to_string(&references)?;
+    save_string_to_file(input, "test_data/lru_miss_ratio_curve_input.json")?;
+    save_results_to_file(curve, "test_data/lru_miss_ratio_curve_output.csv")?;
+    save_results_to_file(histogram, "test_data/lru_reuse_distance_histogram.csv")?;
+    Ok(())
+}
+
+/// Looks for short reference strings on which FIFO gets more page faults
+/// with more frames, and checks the same space for least recently used.
+/// With 5 pages, the textbook string is the only one of 12 references
+/// up to renaming the pages.
+pub fn belady_anomaly_search() -> Result<(), Error> {
+    let exhaustive_config = || BeladySearchConfig {
+        pages_count: 5,
+        references_length: 12,
+        memory_sizes: 1..=5,
+        strategy: BeladySearchStrategy::Exhaustive,
+        max_examples: 20,
+    };
+    let fifo_exhaustive_results = search_belady_anomalies(fifo, exhaustive_config())?;
+    let fifo_random_results = search_belady_anomalies(
+        fifo,
+        BeladySearchConfig {
+            pages_count: 5,
+            references_length: 20,
+            memory_sizes: 1..=5,
+            strategy: BeladySearchStrategy::Random {
+                attempts: 100_000,
+                seed: SEED,
+            },
+            max_examples: 20,
+        },
+    )?;
+    let least_recently_used_results =
+        search_belady_anomalies(least_recently_used, exhaustive_config())?;
+
+    let mut output = "References,Smaller memory size,Misses,Larger memory size,Misses".to_owned();
+    let examples = fifo_exhaustive_results
+        .examples
+        .iter()
+        .chain(&fifo_random_results.examples);
+    for example in examples {
+        let references: Vec<String> = example.references.iter().map(|x| x.to_string()).collect();
+        for anomaly in &example.anomalies {
+            output += &format!(
+                "\n{},{},{},{},{}",
+                references.join(" "),
+                anomaly.smaller_memory_size,
+                anomaly.smaller_memory_misses,
+                anomaly.larger_memory_size,
+                anomaly.larger_memory_misses
+            );
+        }
+    }
+    let summary = format!(
+        "Policy,Search,Checked reference strings,Reference strings with anomaly\nFIFO,Exhaustive,{},{}\nFIFO,Random,{},{}\nLeast recently used,Exhaustive,{},{}",
+        fifo_exhaustive_results.checked_reference_strings,
+        fifo_exhaustive_results.examples.len(),
+        fifo_random_results.checked_reference_strings,
+        fifo_random_results.examples.len(),
+        least_recently_used_results.checked_reference_strings,
+        least_recently_used_results.examples.len()
+    );
+    save_results_to_file(output, "test_data/belady_anomaly_output.csv")?;
+    save_results_to_file(summary, "test_data/belady_anomaly_summary.csv")?;
+    Ok(())
+}
+
+/// Textbook reference string diagrams, ready to be put into slides
+pub fn paging_diagrams() -> Result<(), Error> {
+    let references = vec![7, 0, 1, 2, 0, 3, 0, 4, 2, 3, 0, 3, 2, 1, 2, 0, 1, 7, 0, 1];
+    let fifo = PagingSimulator::new(3, Fifo::default())?
+        .with_trace()
+        .run(references.clone());
+    let least_recently_used = PagingSimulator::new(3, LeastRecentlyUsed::default())?
+        .with_trace()
+        .run(references);
+    for (name, results) in [("fifo", fifo), ("lru", least_recently_used)] {
+        let path = format!("test_data/{}_diagram", name);
+        save_string_to_file(results.trace_to_ascii(), &format!("{}.txt", path))?;
+        save_string_to_file(results.trace_to_markdown(), &format!("{}.md", path))?;
+        save_string_to_file(results.trace_to_latex(), &format!("{}.tex", path))?;
+    }
+    Ok(())
+}
+
+/// Effective access time of walking a 256 KiB array with different
+/// strides, page sizes and TLB sizes
+pub fn address_translation() -> Result<(), Error> {
+    let array_size: u64 = 256 * 1024;
+    let mut output = "Stride,Page size,TLB entries,TLB hit rate,Page table walks,Page faults,Effective access time".to_owned();
+    for stride in [8, 512, 4096] {
+        let addresses: Vec<u64> = (0..4)
+            .flat_map(|_| (0..array_size).step_by(stride))
+            .collect();
+        for page_size in [1024_u64, 4096] {
+            for tlb_entries in [16, 64] {
+                // 32-bit address space split between two page table levels
+                let page_number_bits = 32 - page_size.trailing_zeros();
+                let config = AddressTranslationConfig {
+                    page_size,
+                    page_table_levels: vec![
+                        page_number_bits / 2,
+                        page_number_bits - page_number_bits / 2,
+                    ],
+                    tlb: TlbConfig {
+                        entries: tlb_entries,
+                        associativity: 4,
+                        replacement: TlbReplacement::LeastRecentlyUsed,
+                    },
+                    latencies: AccessLatencies {
+                        tlb: 1.0,
+                        memory: 100.0,
+                        page_fault: 8_000_000.0,
+                    },
+                };
+                let results = translate_addresses(
+                    addresses.clone(),
+                    64,
+                    LeastRecentlyUsed::default(),
+                    config,
+                )?;
+                output += &format!(
+                    "\n{},{},{},{},{},{},{}",
+                    stride,
+                    page_size,
+                    tlb_entries,
+                    results.tlb_hit_rate,
+                    results.page_table_walks,
+                    results.paging.misses,
+                    results.effective_access_time
+                );
+            }
+        }
+    }
+    save_results_to_file(output, "test_data/address_translation_output.csv")?;
+    Ok(())
+}
+
+/// Two processes with small working sets sharing memory with a process
+/// sweeping through a large array, under local and global least recently used
+pub fn local_vs_global_replacement() -> Result<(), Error> {
+    let get_processes = || -> Result<Vec<PagingProcess>, Error> {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        Ok(vec![
+            PagingProcess {
+                name: "Editor".to_owned(),
+                references: generate_normal_distribution_of_references(12, 40.0, 5.0, &mut rng)?,
+            },
+            PagingProcess {
+                name: "Shell".to_owned(),
+                references: generate_normal_distribution_of_references(8, 60.0, 5.0, &mut rng)?,
+            },
+            PagingProcess {
+                name: "Hungry".to_owned(),
+                references: (0..1000).map(|x| x % 200).collect(),
+            },
+        ])
+    };
+    let mut output = "Replacement,Allocation,Process,Allocated frames,Misses,Fault rate".to_owned();
+    let variants = [
+        (ReplacementScope::Local, FrameAllocation::Equal),
+        (ReplacementScope::Local, FrameAllocation::Proportional),
+        (ReplacementScope::Global, FrameAllocation::Equal),
+    ];
+    for (scope, allocation) in variants {
+        let config = MultiprocessPagingConfig {
+            physical_memory_size: 48,
+            allocation,
+            scope,
+            schedule: InterleavingSchedule::RoundRobin { quantum: 4 },
+        };
+        let results = simulate_multiprocess_paging(get_processes()?, config, |_| {
+            LeastRecentlyUsed::default()
+        })?;
+        let scope = match scope {
+            ReplacementScope::Local => "Local",
+            ReplacementScope::Global => "Global",
+        };
+        let allocation = match allocation {
+            FrameAllocation::Equal => "Equal",
+            FrameAllocation::Proportional => "Proportional",
+        };
+        for process in results.processes {
+            let allocated_frames = match process.allocated_frames {
+                Some(frames) => frames.to_string(),
+                None => "-".to_owned(),
+            };
+            output += &format!(
+                "\n{},{},{},{},{},{}",
+                scope,
+                allocation,
+                process.name,
+                allocated_frames,
+                process.misses,
+                process.fault_rate
+            );
+        }
+    }
+    save_results_to_file(output, "test_data/local_vs_global_replacement_output.csv")?;
+    Ok(())
+}
+
+/// CPU utilization against the degree of multiprogramming. Once the working
+/// sets stop fitting into memory, processes spend most of the time waiting
+/// for the paging device and utilization collapses.
+pub fn thrashing() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut queue = generate_queue(16, 400.0, 50.0, &mut rng)?;
+    for process in queue.iter_mut() {
+        process.arrival_time = 0.0;
+        // Every process moves through 8 phases, each with a working set of 6 pages
+        let mut references = vec![];
+        for phase in 0..8 {
+            let phase_references =
+                generate_normal_distribution_of_references(6, 10.0, 2.0, &mut rng)?;
+            references.extend(phase_references.into_iter().map(|page| page + phase * 6));
+        }
+        process.page_references = Some(references);
+    }
+    let config = VirtualMemoryConfig {
+        physical_memory_size: 32,
+        processing_time: 10.0,
+        page_fault_latency: 4.0,
+    };
+    let results = sweep_multiprogramming_degree(&queue, config, LeastRecentlyUsed::default)?;
+
+    let mut output =
+        "Degree of multiprogramming,CPU utilization,Page faults,Total execution time".to_owned();
+    for (i, result) in results.iter().enumerate() {
+        output += &format!(
+            "\n{},{},{},{}",
+            i + 1,
+            result.cpu_utilization,
+            result.page_faults,
+            result.total_execution_time
+        );
+    }
+    save_processes_list_to_path(&queue, "test_data/thrashing_input.json")?;
+    save_results_to_file(output, "test_data/thrashing_output.csv")?;
+    Ok(())
+}
+
+/// Compares FIFO and LRU on reference strings with different kinds of locality.
+/// Every string is generated from the same seed, so the results can be reproduced.
+pub fn locality_generators() -> Result<(), Error> {
+    let references_count = 2000;
+    let physical_memory_size = 16;
+    // Mostly walks forward through 32 pages, sometimes jumping back to the first one
+    let markov_transitions: Vec<Vec<f32>> = (0..32)
+        .map(|page| {
+            (0..32)
+                .map(|next| match next {
+                    0 => 0.1,
+                    next if next == (page + 1) % 32 => 0.9,
+                    _ => 0.0,
+                })
+                .collect()
+        })
+        .collect();
+    let generators: Vec<(&str, Box<dyn ReferenceGenerator>)> = vec![
+        (
+            "Phased working set",
+            Box::new(PhasedWorkingSet::new(128, 12, 200)?),
+        ),
+        ("Zipf", Box::new(ZipfPopularity::new(128, 1.0)?)),
+        ("Sequential scan", Box::new(Scan::sequential(0, 128))),
+        ("Looping scan", Box::new(Scan::looping(0, 20))),
+        (
+            "Markov chain",
+            Box::new(MarkovChain::new(markov_transitions, 0)?),
+        ),
+        (
+            "90% hot set + 10% scan",
+            Box::new(Mix::new(vec![
+                (0.9, Box::new(PhasedWorkingSet::new(12, 12, 1)?)),
+                (0.1, Box::new(Scan::sequential(100, 1000))),
+            ])?),
+        ),
+    ];
+
+    let mut all_references: Vec<Vec<u64>> = vec![];
+    let mut output = "Generator,FIFO misses,LRU misses".to_owned();
+    for (name, mut generator) in generators {
+        let references = generate_references(
+            generator.as_mut(),
+            references_count,
+            &mut StdRng::seed_from_u64(SEED),
+        );
+        all_references.push(references.clone());
+        let fifo = fifo(physical_memory_size, references.clone())?;
+        let least_recently_used = least_recently_used(physical_memory_size, references)?;
+        output += &format!("\n{},{},{}", name, fifo.misses, least_recently_used.misses);
+    }
+
+    let input = serde_json::to_string(&all_references)?;
+    save_string_to_file(input, "test_data/locality_generators_input.json")?;
+    save_results_to_file(output, "test_data/locality_generators_output.csv")?;
+    Ok(())
+}
+
+/// LRU misses of simple programs for a growing number of frames,
+/// showing how much loop order and data layout matter
+pub fn program_traces() -> Result<(), Error> {
+    let layout = MemoryLayout {
+        page_size: 512,
+        element_size: 8,
+    };
+    let programs = vec![
+        (
+            "Row-major traversal",
+            row_major_traversal(128, 128, layout)?,
+        ),
+        (
+            "Column-major traversal",
+            column_major_traversal(128, 128, layout)?,
+        ),
+        ("Naive matrix multiply", matrix_multiply(20, layout)?),
+        (
+            "Blocked matrix multiply",
+            blocked_matrix_multiply(20, 8, layout)?,
+        ),
+        (
+            "Linked list walk",
+            linked_list_walk(16384, layout, &mut StdRng::seed_from_u64(SEED))?,
+        ),
+        (
+            "Binary search",
+            binary_search(16384, 1000, layout, &mut StdRng::seed_from_u64(SEED))?,
+        ),
+    ];
+
+    let mut output = "Program,References".to_owned();
+    let memory_sizes: Vec<usize> = (2..=16).collect();
+    for physical_memory_size in &memory_sizes {
+        output += &format!(",LRU misses ({} frames)", physical_memory_size);
+    }
+    for (name, trace) in programs {
+        let references = trace.page_references();
+        output += &format!("\n{},{}", name, references.len());
+        for physical_memory_size in &memory_sizes {
+            let results = least_recently_used(*physical_memory_size, references.clone())?;
+            output += &format!(",{}", results.misses);
+        }
+    }
+    save_results_to_file(output, "test_data/program_traces_output.csv")?;
+    Ok(())
+}
+
+/// FIFO vs LRU once dirty evictions cost a write-back,
+/// for a reference string where 30% of references are writes
+pub fn dirty_page_write_back() -> Result<(), Error> {
+    let references = generate_references(
+        &mut PhasedWorkingSet::new(128, 12, 200)?,
+        5000,
+        &mut StdRng::seed_from_u64(SEED),
+    );
+    let accesses = tag_writes(references, 0.3, &mut StdRng::seed_from_u64(SEED))?;
+    let costs = || IoCostModel {
+        memory_access: 0.1,
+        fault_service: 8000.0,
+        write_back: 8000.0,
+    };
+
+    let mut output = "Physical memory size,FIFO misses,FIFO write-backs,FIFO I/O time,FIFO EAT,\
+LRU misses,LRU write-backs,LRU I/O time,LRU EAT"
+        .to_owned();
+    for physical_memory_size in 4..=16 {
+        let fifo = simulate_write_back(
+            accesses.clone(),
+            physical_memory_size,
+            Fifo::default(),
+            costs(),
+        )?;
+        let least_recently_used = simulate_write_back(
+            accesses.clone(),
+            physical_memory_size,
+            LeastRecentlyUsed::default(),
+            costs(),
+        )?;
+        output += &format!("\n{}", physical_memory_size);
+        for results in [fifo, least_recently_used] {
+            output += &format!(
+                ",{},{},{},{}",
+                results.paging.misses,
+                results.dirty_evictions,
+                results.total_io_time,
+                results.effective_access_time
+            );
+        }
+    }
+    save_results_to_file(output, "test_data/dirty_page_write_back_output.csv")?;
+    Ok(())
+}
+
+/// Average waiting time against the offered load ρ = λ * mean burst time,
+/// for arrivals with the same average rate but different burstiness
+pub fn offered_load_sweep() -> Result<(), Error> {
+    let mean_burst_time = 10.0;
+    let bursts = BurstDistribution::FoldedNormal {
+        mean: mean_burst_time,
+        standard_deviation: 3.0,
+    };
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut output =
+        "Offered load,Arrivals,FCFS avg waiting time,Round-robin avg waiting time".to_owned();
+    for load in (1..=9).map(|x| x as f32 / 10.0) {
+        let rate = load / mean_burst_time;
+        let arrival_processes: Vec<(&str, Box<dyn ArrivalProcess>)> = vec![
+            ("Periodic", Box::new(Periodic::new(1.0 / rate, 0.1 / rate)?)),
+            (
+                "Poisson",
+                Box::new(Poisson::with_load(load, mean_burst_time)?),
+            ),
+            // Bursts at 4 times the average rate, a quarter of the time
+            (
+                "On/off bursts",
+                Box::new(OnOffBursts::new(4.0 * rate, 0.0, 50.0, 150.0)?),
+            ),
+        ];
+        for (name, mut arrivals) in arrival_processes {
+            let queue = generate_queue_with_arrivals(500, &bursts, arrivals.as_mut(), &mut rng)?;
+            let fcfs = fcfs(queue.clone());
+            let robin = round_robin(queue, mean_burst_time / 2.0)?;
+            output += &format!(
+                "\n{},{},{},{}",
+                load, name, fcfs.average_waiting_time, robin.average_waiting_time
+            );
+        }
+    }
+    save_results_to_file(output, "test_data/offered_load_sweep_output.csv")?;
+    Ok(())
+}
+
+/// FCFS vs round-robin for burst time distributions with the same mean,
+/// from light to heavy tails, at the same offered load
+pub fn heavy_tailed_bursts() -> Result<(), Error> {
+    let load = 0.7;
+    let mean_burst_time = 10.0;
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let distributions = vec![
+        (
+            "Truncated normal",
+            BurstDistribution::TruncatedNormal {
+                mean: mean_burst_time,
+                standard_deviation: 3.0,
+                min: 0.0,
+            },
+        ),
+        (
+            "Exponential",
+            BurstDistribution::Exponential {
+                mean: mean_burst_time,
+            },
+        ),
+        (
+            "Log-normal",
+            BurstDistribution::LogNormal {
+                mean: mean_burst_time,
+                standard_deviation: 30.0,
+            },
+        ),
+        (
+            "Hyperexponential",
+            BurstDistribution::Hyperexponential {
+                phases: vec![(0.95, 5.0), (0.05, 105.0)],
+            },
+        ),
+        (
+            "Pareto",
+            BurstDistribution::Pareto {
+                scale: mean_burst_time / 3.0,
+                shape: 1.5,
+            },
+        ),
+    ];
+
+    let mut output =
+        "Distribution,Mean burst time,Sample mean burst time,FCFS avg waiting time,Round-robin avg waiting time"
+            .to_owned();
+    for (name, distribution) in distributions {
+        let mut arrivals = Poisson::with_load(load, distribution.mean())?;
+        let queue = generate_queue_with_arrivals(1000, &distribution, &mut arrivals, &mut rng)?;
+        let sample_mean = queue.iter().map(|x| x.burst_time).sum::<f32>() / queue.len() as f32;
+        let fcfs = fcfs(queue.clone());
+        let robin = round_robin(queue, 2.0)?;
+        output += &format!(
+            "\n{},{},{},{},{}",
+            name,
+            distribution.mean(),
+            sample_mean,
+            fcfs.average_waiting_time,
+            robin.average_waiting_time
+        );
+    }
+    save_results_to_file(output, "test_data/heavy_tailed_bursts_output.csv")?;
+    Ok(())
+}
+
+/// Generates the workload described by test_data/workload_spec.json and
+/// compares FCFS with round-robin on it, along with per-population statistics
+pub fn workload_from_spec() -> Result<(), Error> {
+    let spec = read_workload_spec_from("test_data/workload_spec.json")?;
+    let queue = generate_workload(&spec)?;
+    save_processes_list_to_path(&queue, "test_data/workload_spec_processes.json")?;
+
+    let mut output = "Population,Processes count,Mean burst time".to_owned();
+    for population in &spec.populations {
+        let prefix = format!("{}_", population.name);
+        let bursts: Vec<f32> = queue
+            .iter()
+            .filter(|x| x.name.starts_with(&prefix))
+            .map(|x| x.burst_time)
+            .collect();
+        output += &format!(
+            "\n{},{},{}",
+            population.name,
+            bursts.len(),
+            bursts.iter().sum::<f32>() / bursts.len() as f32
+        );
+    }
+    output += &format!(
+        "\n\nFCFS avg waiting time,Round-robin avg waiting time\n{},{}",
+        fcfs(queue.clone()).average_waiting_time,
+        round_robin(queue, 2.0)?.average_waiting_time
+    );
+    // The spec has its own seed, which is the one reproducing the workload
+    save_results_with_seed(output, spec.seed, "test_data/workload_from_spec_output.csv")?;
+    Ok(())
+}
+
+pub fn swf_replay() -> Result<(), Error> {
+    let records = read_swf_from("test_data/swf_sample.swf")?;
+    let filters = vec![
+        ("All records", SwfFilter::default()),
+        (
+            "Valid records",
+            SwfFilter {
+                drop_invalid: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "Completed jobs",
+            SwfFilter {
+                drop_invalid: true,
+                completed_only: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "First hour",
+            SwfFilter {
+                time_window: Some((0.0, 3600.0)),
+                drop_invalid: true,
+                completed_only: false,
+            },
+        ),
+    ];
+
+    let mut output =
+        "Filter,Jobs count,FCFS avg waiting time,Round-robin avg waiting time".to_owned();
+    for (name, filter) in filters {
+        let queue = swf_to_processes(&records, &filter)?;
+        output += &format!(
+            "\n{},{},{},{}",
+            name,
+            queue.len(),
+            fcfs(queue.clone()).average_waiting_time,
+            round_robin(queue, 60.0)?.average_waiting_time
+        );
+    }
+    save_results_to_file(output, "test_data/swf_replay_output.csv")?;
+
+    // Generated queues can be exported for tools reading the archive's format
+    let spec = read_workload_spec_from("test_data/workload_spec.json")?;
+    let queue = generate_workload(&spec)?;
+    save_swf_to_path(&queue, "test_data/workload_spec_processes.swf")?;
+    Ok(())
+}
+
+/// Runs a small queue as real processes pinned to one CPU,
+/// 10ms per time unit, next to the simulated schedulers
+#[cfg(target_os = "linux")]
+pub fn ground_truth_validation() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut queue = generate_queue(10, 5.0, 2.0, &mut rng)?;
+    // Random priorities would mostly map to nice 19, run every worker at nice 0
+    for process in &mut queue {
+        process.priority = None;
+    }
+    let config = GroundTruthConfig {
+        time_unit: Duration::from_millis(10),
+        pinned_cpu: Some(0),
+        round_robin_quantum: 1.0,
+    };
+    let results = compare_with_simulations(queue, &config)?;
+    save_results_to_file(
+        results.to_csv_string(),
+        "test_data/ground_truth_validation_output.csv",
+    )?;
+    Ok(())
+}
+
+/// Saves experiment results, preceded by a comment line
+/// with the seed they were generated from
+fn save_results_to_file(results: String, path: &str) -> Result<(), Error> {
+    save_results_with_seed(results, SEED, path)
+}
+
+/// Same as `save_results_to_file`, for results generated from another seed
+fn save_results_with_seed(results: String, seed: u64, path: &str) -> Result<(), Error> {
+    save_string_to_file(format!("# Seed: {}\n{}", seed, results), path)
+}
+
+fn save_string_to_file(string: String, path: &str) -> Result<(), Error> {
+    File::create(path)
+        .and_then(|mut file| file.write_all(string.as_bytes()))
+        .map_err(|error| Error::from(error).in_file(path))
+}
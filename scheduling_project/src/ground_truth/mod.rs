@@ -0,0 +1,214 @@
+use std::{
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::Error,
+    process::{procfs::priority_to_nice, ProcessSpec},
+    scheduling::{fcfs, round_robin},
+};
+
+/// Burns CPU until the shell itself has run for $1 nanoseconds,
+/// then reports its run time and run queue wait from schedstat
+const WORKER_SCRIPT: &str = r#"
+while :; do
+    read run wait rest < /proc/$$/schedstat
+    if [ "$run" -ge "$1" ]; then
+        echo "$run $wait"
+        exit 0
+    fi
+done
+"#;
+
+pub struct GroundTruthConfig {
+    /// Real duration of one simulated time unit
+    pub time_unit: Duration,
+    /// Pins every worker to this CPU with `taskset`, so that they
+    /// compete for a single CPU as in the simulations
+    pub pinned_cpu: Option<usize>,
+    pub round_robin_quantum: f32,
+}
+
+/// What a worker actually went through, in simulated time units
+pub struct WorkerMeasurement {
+    pub name: String,
+    pub nice: i32,
+    pub arrival_time: f32,
+    pub burst_time: f32,
+    /// CPU time the worker really got, slightly above its burst time
+    pub cpu_time: f32,
+    pub turnaround_time: f32,
+    /// Turnaround time not spent on a CPU, as in the simulations
+    pub waiting_time: f32,
+    /// Time spent runnable but not running, as reported by schedstat
+    pub run_queue_wait: f32,
+}
+
+/// Averages measured on the kernel next to the simulated ones
+pub struct GroundTruthResults {
+    pub workers: Vec<WorkerMeasurement>,
+    /// (name, average waiting time, average turnaround time) for
+    /// the kernel and every simulated scheduler
+    pub averages: Vec<(String, f32, f32)>,
+}
+
+impl GroundTruthResults {
+    pub fn to_csv_string(&self) -> String {
+        let mut output = "Scheduler,Average waiting time,Average turnaround time".to_owned();
+        for (name, waiting_time, turnaround_time) in &self.averages {
+            output += &format!("\n{},{},{}", name, waiting_time, turnaround_time);
+        }
+        output += "\n\nProcess,Nice,Arrival time,Burst time,CPU time,Turnaround time,Waiting time,Run queue wait";
+        for worker in &self.workers {
+            output += &format!(
+                "\n{},{},{},{},{},{},{},{}",
+                worker.name,
+                worker.nice,
+                worker.arrival_time,
+                worker.burst_time,
+                worker.cpu_time,
+                worker.turnaround_time,
+                worker.waiting_time,
+                worker.run_queue_wait
+            );
+        }
+        output
+    }
+}
+
+/// Spawns a busy-looping child process for every process of the queue, each one
+/// started at its arrival time with its priority as nice value (see
+/// `priority_to_nice`), and measures how long the kernel took to run them.
+/// Raising priority above nice 0 needs privileges, `nice` warns and runs
+/// the worker at the default niceness otherwise.
+pub fn run_workers(
+    queue: &[ProcessSpec],
+    config: &GroundTruthConfig,
+) -> Result<Vec<WorkerMeasurement>, Error> {
+    let time_unit = config.time_unit.as_secs_f32();
+    let start = Instant::now();
+    let handles: Vec<_> = queue
+        .iter()
+        .map(|process| {
+            let nice = process.priority.map_or(0, priority_to_nice);
+            let mut command = match config.pinned_cpu {
+                Some(cpu) => {
+                    let mut command = Command::new("taskset");
+                    command.args(["--cpu-list", &cpu.to_string(), "nice"]);
+                    command
+                }
+                None => Command::new("nice"),
+            };
+            let burst_nanoseconds = (process.burst_time * time_unit * 1e9) as u64;
+            command
+                .args(["-n", &nice.to_string(), "sh", "-c", WORKER_SCRIPT, "worker"])
+                .arg(burst_nanoseconds.to_string())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null());
+            let arrival = Duration::from_secs_f32(process.arrival_time.max(0.0) * time_unit);
+            let process = process.clone();
+            thread::spawn(move || -> Result<WorkerMeasurement, Error> {
+                thread::sleep(arrival.saturating_sub(start.elapsed()));
+                let spawned = Instant::now();
+                let output = command
+                    .spawn()
+                    .and_then(|child| child.wait_with_output())
+                    .map_err(|error| {
+                        Error::from(error).in_file(command.get_program().to_str().unwrap_or(""))
+                    })?;
+                let turnaround_time = spawned.elapsed().as_secs_f32() / time_unit;
+                let report = String::from_utf8_lossy(&output.stdout);
+                let (cpu_time, run_queue_wait) = report
+                    .trim()
+                    .split_once(' ')
+                    .and_then(|(run, wait)| {
+                        Some((run.parse::<u64>().ok()?, wait.parse::<u64>().ok()?))
+                    })
+                    .ok_or_else(|| {
+                        Error::configuration(format!(
+                            "worker {} reported '{}', is /proc/<pid>/schedstat available?",
+                            process.name,
+                            report.trim()
+                        ))
+                    })?;
+                let cpu_time = cpu_time as f32 / 1e9 / time_unit;
+                Ok(WorkerMeasurement {
+                    name: process.name,
+                    nice,
+                    arrival_time: process.arrival_time,
+                    burst_time: process.burst_time,
+                    cpu_time,
+                    turnaround_time,
+                    waiting_time: (turnaround_time - cpu_time).max(0.0),
+                    run_queue_wait: run_queue_wait as f32 / 1e9 / time_unit,
+                })
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|handle| handle.join().expect("worker thread panicked"))
+        .collect()
+}
+
+/// Runs the queue on the kernel and through the FCFS and round-robin simulations
+pub fn compare_with_simulations(
+    queue: Vec<ProcessSpec>,
+    config: &GroundTruthConfig,
+) -> Result<GroundTruthResults, Error> {
+    let workers = run_workers(&queue, config)?;
+    let count = workers.len().max(1) as f32;
+    let mean_burst_time = queue.iter().map(|x| x.burst_time).sum::<f32>() / count;
+    let mut averages = vec![(
+        "Linux".to_owned(),
+        workers.iter().map(|x| x.waiting_time).sum::<f32>() / count,
+        workers.iter().map(|x| x.turnaround_time).sum::<f32>() / count,
+    )];
+    // Every process waits for its turnaround time minus its burst time
+    let simulations = [
+        ("FCFS", fcfs(queue.clone())),
+        (
+            "Round-robin",
+            round_robin(queue, config.round_robin_quantum)?,
+        ),
+    ];
+    for (name, results) in simulations {
+        averages.push((
+            name.to_owned(),
+            results.average_waiting_time,
+            results.average_waiting_time + mean_burst_time,
+        ));
+    }
+    Ok(GroundTruthResults { workers, averages })
+}
+
+#[cfg(test)]
+mod ground_truth_tests {
+    use super::*;
+    use crate::process::create;
+
+    #[test]
+    #[ignore = "spawns real processes pinned to CPU 0, needs taskset and an idle machine"]
+    fn test_run_workers() {
+        let config = GroundTruthConfig {
+            time_unit: Duration::from_millis(10),
+            pinned_cpu: Some(0),
+            round_robin_quantum: 1.0,
+        };
+        let queue = vec![create("A", 0.0, 3.0, None), create("B", 1.0, 2.0, Some(25))];
+        let results = compare_with_simulations(queue, &config).unwrap();
+        let workers = &results.workers;
+        assert!(workers.len() == 2);
+        assert!(workers[1].name == "B" && workers[1].nice == 5);
+        for worker in workers {
+            assert!(worker.cpu_time >= worker.burst_time);
+            assert!(worker.turnaround_time >= worker.cpu_time);
+        }
+        let names: Vec<&str> = results.averages.iter().map(|x| x.0.as_str()).collect();
+        assert!(names == vec!["Linux", "FCFS", "Round-robin"]);
+        // FCFS: B waits for A from 1 to 3, turnarounds are 3 and 4
+        assert!(results.averages[1] == ("FCFS".to_owned(), 1.0, 3.5));
+    }
+}
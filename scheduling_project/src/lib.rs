@@ -0,0 +1,10 @@
+pub mod error;
+pub mod experiment;
+#[cfg(target_os = "linux")]
+pub mod ground_truth;
+pub mod paging;
+pub mod process;
+pub mod scheduling;
+pub mod validation;
+pub mod virtual_memory;
+pub mod workload;
@@ -1,11 +1,67 @@
-mod experiment;
-mod paging;
-mod process;
-mod scheduling;
+use scheduling_project::{error::Error, experiment};
+
+type Experiment = fn() -> Result<(), Error>;
 
 fn main() {
-    experiment::round_robin_processing_time();
-    experiment::fcfs_vs_round_robin();
-    experiment::convoy_effect();
-    experiment::fifo_vs_least_recently_used();
+    #[allow(unused_mut)]
+    let mut experiments: Vec<(&str, Experiment)> = vec![
+        (
+            "round_robin_processing_time",
+            experiment::round_robin_processing_time,
+        ),
+        ("fcfs_vs_round_robin", experiment::fcfs_vs_round_robin),
+        ("convoy_effect", experiment::convoy_effect),
+        (
+            "fifo_vs_least_recently_used",
+            experiment::fifo_vs_least_recently_used,
+        ),
+        ("scan_resistance", experiment::scan_resistance),
+        (
+            "working_set_size_over_time",
+            experiment::working_set_size_over_time,
+        ),
+        (
+            "page_fault_frequency_allocation",
+            experiment::page_fault_frequency_allocation,
+        ),
+        (
+            "least_recently_used_miss_ratio_curve",
+            experiment::least_recently_used_miss_ratio_curve,
+        ),
+        ("belady_anomaly_search", experiment::belady_anomaly_search),
+        ("paging_diagrams", experiment::paging_diagrams),
+        ("address_translation", experiment::address_translation),
+        (
+            "local_vs_global_replacement",
+            experiment::local_vs_global_replacement,
+        ),
+        ("thrashing", experiment::thrashing),
+        ("locality_generators", experiment::locality_generators),
+        ("program_traces", experiment::program_traces),
+        ("dirty_page_write_back", experiment::dirty_page_write_back),
+        ("offered_load_sweep", experiment::offered_load_sweep),
+        ("heavy_tailed_bursts", experiment::heavy_tailed_bursts),
+        ("workload_from_spec", experiment::workload_from_spec),
+        ("swf_replay", experiment::swf_replay),
+    ];
+    // Spawns real processes and takes a while, so it only runs when asked for
+    #[cfg(target_os = "linux")]
+    if std::env::args().any(|x| x == "--ground-truth") {
+        experiments.push((
+            "ground_truth_validation",
+            experiment::ground_truth_validation,
+        ));
+    }
+
+    // A failing experiment doesn't stop the other ones
+    let mut failures = 0;
+    for (name, run) in experiments {
+        if let Err(error) = run() {
+            eprintln!("{}: {}", name, error);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
 }
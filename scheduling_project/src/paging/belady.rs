@@ -0,0 +1,173 @@
+use std::ops::RangeInclusive;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::PagingSimulationResults;
+use crate::error::Error;
+
+/// A pair of neighbouring physical memory sizes
+/// where adding a frame resulted in more page faults
+pub struct BeladyAnomaly {
+    pub smaller_memory_size: usize,
+    pub smaller_memory_misses: usize,
+    pub larger_memory_size: usize,
+    pub larger_memory_misses: usize,
+}
+
+/// Results of running a replacement policy across a range of memory sizes
+pub struct BeladyResults {
+    pub references: Vec<u64>,
+    /// Physical memory sizes paired with the number of misses
+    pub misses: Vec<(usize, usize)>,
+    pub anomalies: Vec<BeladyAnomaly>,
+}
+
+/// How reference strings are picked when looking for Belady's anomaly
+pub enum BeladySearchStrategy {
+    /// Checks given number of uniformly random reference strings,
+    /// generated from the given seed
+    Random { attempts: usize, seed: u64 },
+    /// Checks every reference string of given length over given pages,
+    /// up to renaming the pages
+    Exhaustive,
+}
+
+/// Parameters of a Belady's anomaly search
+pub struct BeladySearchConfig {
+    pub pages_count: u64,
+    pub references_length: usize,
+    pub memory_sizes: RangeInclusive<usize>,
+    pub strategy: BeladySearchStrategy,
+    /// The search stops after finding this many examples
+    pub max_examples: usize,
+}
+
+/// Results of a Belady's anomaly search.
+/// An empty list of examples after an exhaustive search proves
+/// the anomaly can't happen for given pages count and references length.
+pub struct BeladySearchResults {
+    pub checked_reference_strings: usize,
+    pub examples: Vec<BeladyResults>,
+}
+
+/// Runs a replacement policy for every memory size in the range
+/// and flags every place where an extra frame caused more misses.
+/// The policy is given the physical memory size and references,
+/// e.g. `fifo` or `least_recently_used`.
+pub fn detect_belady_anomalies<F>(
+    policy: F,
+    references: &[u64],
+    memory_sizes: RangeInclusive<usize>,
+) -> Result<BeladyResults, Error>
+where
+    F: Fn(usize, Vec<u64>) -> Result<PagingSimulationResults, Error>,
+{
+    let misses: Vec<(usize, usize)> = memory_sizes
+        .map(|size| Ok((size, policy(size, references.to_vec())?.misses)))
+        .collect::<Result<_, Error>>()?;
+    let anomalies = misses
+        .windows(2)
+        .filter(|pair| pair[1].1 > pair[0].1)
+        .map(|pair| BeladyAnomaly {
+            smaller_memory_size: pair[0].0,
+            smaller_memory_misses: pair[0].1,
+            larger_memory_size: pair[1].0,
+            larger_memory_misses: pair[1].1,
+        })
+        .collect();
+    Ok(BeladyResults {
+        references: references.to_vec(),
+        misses,
+        anomalies,
+    })
+}
+
+/// Looks for short reference strings exhibiting Belady's anomaly.
+/// For stack algorithms, like least recently used, it should never find any.
+pub fn search_belady_anomalies<F>(
+    policy: F,
+    config: BeladySearchConfig,
+) -> Result<BeladySearchResults, Error>
+where
+    F: Fn(usize, Vec<u64>) -> Result<PagingSimulationResults, Error>,
+{
+    let mut examples: Vec<BeladyResults> = vec![];
+    let mut checked_reference_strings = 0;
+    let mut check = |references: &[u64]| -> Result<bool, Error> {
+        checked_reference_strings += 1;
+        let results = detect_belady_anomalies(&policy, references, config.memory_sizes.clone())?;
+        if !results.anomalies.is_empty() {
+            examples.push(results);
+        }
+        Ok(examples.len() < config.max_examples)
+    };
+
+    match config.strategy {
+        BeladySearchStrategy::Random { attempts, seed } => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..attempts {
+                let references: Vec<u64> = (0..config.references_length)
+                    .map(|_| rng.random_range(0..config.pages_count))
+                    .collect();
+                if !check(&references)? {
+                    break;
+                }
+            }
+        }
+        BeladySearchStrategy::Exhaustive => {
+            // Renaming pages doesn't change the number of misses, so only strings
+            // where pages are first referenced in ascending order are checked
+            let mut references: Vec<u64> = vec![0; config.references_length];
+            loop {
+                if !check(&references)? {
+                    break;
+                }
+                let Some(position) = (1..references.len()).rev().find(|i| {
+                    let highest_page = references[..*i].iter().max().unwrap();
+                    references[*i] <= *highest_page && references[*i] + 1 < config.pages_count
+                }) else {
+                    break;
+                };
+                references[position] += 1;
+                for page in references.iter_mut().skip(position + 1) {
+                    *page = 0;
+                }
+            }
+        }
+    }
+    Ok(BeladySearchResults {
+        checked_reference_strings,
+        examples,
+    })
+}
+
+#[cfg(test)]
+mod belady_tests {
+    use super::*;
+    use crate::paging::{fifo, least_recently_used};
+
+    #[test]
+    fn test_textbook_anomaly() {
+        let references = vec![0, 1, 2, 3, 0, 1, 4, 0, 1, 2, 3, 4];
+        let results = detect_belady_anomalies(fifo, &references, 1..=5).unwrap();
+        assert!(results.anomalies.len() == 1);
+        assert!(results.anomalies[0].smaller_memory_size == 3);
+        assert!(results.anomalies[0].smaller_memory_misses == 9);
+        assert!(results.anomalies[0].larger_memory_misses == 10);
+    }
+
+    #[test]
+    fn test_no_anomaly_for_least_recently_used() {
+        let config = BeladySearchConfig {
+            pages_count: 4,
+            references_length: 7,
+            memory_sizes: 1..=4,
+            strategy: BeladySearchStrategy::Exhaustive,
+            max_examples: 1,
+        };
+        let results = search_belady_anomalies(least_recently_used, config).unwrap();
+        // Stirling numbers of the second kind S(7, k) summed for k = 1..=4
+        assert!(results.checked_reference_strings == 715);
+        assert!(results.examples.is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use rand::{distr::weighted::WeightedIndex, rngs::StdRng, Rng};
+use rand_distr::{Distribution, Zipf};
+
+use crate::error::Error;
+
+/// Source of page references with some kind of locality.
+/// Generators keep their own state (current phase, position in a scan, etc.),
+/// all randomness comes from the RNG passed in, so that runs are reproducible.
+pub trait ReferenceGenerator {
+    fn next_reference(&mut self, rng: &mut StdRng) -> u64;
+}
+
+/// Returns `count` references produced by a generator
+pub fn generate_references(
+    generator: &mut dyn ReferenceGenerator,
+    count: usize,
+    rng: &mut StdRng,
+) -> Vec<u64> {
+    (0..count).map(|_| generator.next_reference(rng)).collect()
+}
+
+/// Program moving through phases, each one uniformly referencing
+/// a different contiguous working set of pages
+pub struct PhasedWorkingSet {
+    pages_count: u64,
+    working_set_size: u64,
+    /// Number of references made before moving to the next phase
+    phase_length: usize,
+    phase_start: u64,
+    phase_position: usize,
+}
+
+impl PhasedWorkingSet {
+    pub fn new(
+        pages_count: u64,
+        working_set_size: u64,
+        phase_length: usize,
+    ) -> Result<PhasedWorkingSet, Error> {
+        if pages_count == 0 {
+            return Err(Error::configuration(
+                "a working set needs at least one page",
+            ));
+        }
+        Ok(PhasedWorkingSet {
+            pages_count,
+            working_set_size: working_set_size.clamp(1, pages_count),
+            phase_length: phase_length.max(1),
+            phase_start: 0,
+            phase_position: 0,
+        })
+    }
+}
+
+impl ReferenceGenerator for PhasedWorkingSet {
+    fn next_reference(&mut self, rng: &mut StdRng) -> u64 {
+        if self.phase_position.is_multiple_of(self.phase_length) {
+            self.phase_start = rng.random_range(0..=self.pages_count - self.working_set_size);
+        }
+        self.phase_position += 1;
+        self.phase_start + rng.random_range(0..self.working_set_size)
+    }
+}
+
+/// Page popularity following Zipf's law, page 0 being the most popular one
+pub struct ZipfPopularity {
+    distribution: Zipf<f64>,
+}
+
+impl ZipfPopularity {
+    /// The higher the exponent, the more references go to the most popular pages
+    pub fn new(pages_count: u64, exponent: f64) -> Result<ZipfPopularity, Error> {
+        let distribution = Zipf::new(pages_count as f64, exponent).map_err(|e| {
+            Error::configuration(format!(
+                "Zipf popularity over {} pages with exponent {}: {}",
+                pages_count, exponent, e
+            ))
+        })?;
+        Ok(ZipfPopularity { distribution })
+    }
+}
+
+impl ReferenceGenerator for ZipfPopularity {
+    fn next_reference(&mut self, rng: &mut StdRng) -> u64 {
+        self.distribution.sample(rng) as u64 - 1
+    }
+}
+
+/// Pages `start`, `start + 1`, ... referenced once each,
+/// or over and over again if `looping` is set
+pub struct Scan {
+    start: u64,
+    length: u64,
+    looping: bool,
+    position: u64,
+}
+
+impl Scan {
+    pub fn sequential(start: u64, length: u64) -> Scan {
+        Scan {
+            start,
+            length: length.max(1),
+            looping: false,
+            position: 0,
+        }
+    }
+
+    pub fn looping(start: u64, length: u64) -> Scan {
+        Scan {
+            looping: true,
+            ..Scan::sequential(start, length)
+        }
+    }
+}
+
+impl ReferenceGenerator for Scan {
+    fn next_reference(&mut self, _rng: &mut StdRng) -> u64 {
+        let reference = self.start + self.position;
+        self.position += 1;
+        if self.position == self.length {
+            self.position = 0;
+            if !self.looping {
+                // A sequential scan keeps going into pages it hasn't seen yet
+                self.start += self.length;
+            }
+        }
+        reference
+    }
+}
+
+/// Next page drawn from the transition probabilities of the current one.
+/// `transitions[i][j]` is the (relative) probability of page j following page i.
+pub struct MarkovChain {
+    transitions: Vec<WeightedIndex<f32>>,
+    current: usize,
+}
+
+impl MarkovChain {
+    pub fn new(transitions: Vec<Vec<f32>>, initial_page: u64) -> Result<MarkovChain, Error> {
+        let pages_count = transitions.len();
+        if initial_page as usize >= pages_count
+            || transitions.iter().any(|row| row.len() > pages_count)
+        {
+            return Err(Error::configuration(format!(
+                "transitions between {} pages can't start at page {} or lead further",
+                pages_count, initial_page
+            )));
+        }
+        let transitions = transitions
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                WeightedIndex::new(row).map_err(|e| {
+                    Error::configuration(format!("transitions from page {}: {}", i, e))
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(MarkovChain {
+            transitions,
+            current: initial_page as usize,
+        })
+    }
+}
+
+impl ReferenceGenerator for MarkovChain {
+    fn next_reference(&mut self, rng: &mut StdRng) -> u64 {
+        let reference = self.current as u64;
+        self.current = self.transitions[self.current].sample(rng);
+        reference
+    }
+}
+
+/// Mixture of generators, every reference comes from a generator
+/// picked with probability proportional to its weight,
+/// e.g. 90% hot set and 10% scan
+pub struct Mix {
+    generators: Vec<Box<dyn ReferenceGenerator>>,
+    weights: WeightedIndex<f32>,
+}
+
+impl Mix {
+    pub fn new(components: Vec<(f32, Box<dyn ReferenceGenerator>)>) -> Result<Mix, Error> {
+        let (weights, generators): (Vec<f32>, Vec<_>) = components.into_iter().unzip();
+        let weights = WeightedIndex::new(weights)
+            .map_err(|e| Error::configuration(format!("generator weights: {}", e)))?;
+        Ok(Mix {
+            generators,
+            weights,
+        })
+    }
+}
+
+impl ReferenceGenerator for Mix {
+    fn next_reference(&mut self, rng: &mut StdRng) -> u64 {
+        let generator = self.weights.sample(rng);
+        self.generators[generator].next_reference(rng)
+    }
+}
+
+#[cfg(test)]
+mod generators_tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_references() {
+        let first = generate_references(
+            &mut ZipfPopularity::new(100, 1.0).unwrap(),
+            1000,
+            &mut StdRng::seed_from_u64(42),
+        );
+        let second = generate_references(
+            &mut ZipfPopularity::new(100, 1.0).unwrap(),
+            1000,
+            &mut StdRng::seed_from_u64(42),
+        );
+        assert!(first == second);
+        assert!(first.iter().all(|x| (0..100).contains(x)));
+    }
+
+    #[test]
+    fn test_scans() {
+        let sequential = generate_references(
+            &mut Scan::sequential(10, 3),
+            7,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert!(sequential == vec![10, 11, 12, 13, 14, 15, 16]);
+        let looping =
+            generate_references(&mut Scan::looping(10, 3), 7, &mut StdRng::seed_from_u64(0));
+        assert!(looping == vec![10, 11, 12, 10, 11, 12, 10]);
+    }
+
+    #[test]
+    fn test_phased_working_set() {
+        let references = generate_references(
+            &mut PhasedWorkingSet::new(64, 4, 50).unwrap(),
+            500,
+            &mut StdRng::seed_from_u64(7),
+        );
+        for phase in references.chunks(50) {
+            let lowest = phase.iter().min().unwrap();
+            let highest = phase.iter().max().unwrap();
+            assert!(highest - lowest < 4);
+        }
+    }
+
+    #[test]
+    fn test_markov_chain() {
+        // 0 -> 1 -> 2 -> 0 with certainty
+        let transitions = vec![
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![1.0, 0.0, 0.0],
+        ];
+        let references = generate_references(
+            &mut MarkovChain::new(transitions, 1).unwrap(),
+            5,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert!(references == vec![1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(ZipfPopularity::new(0, 1.0).is_err());
+        assert!(PhasedWorkingSet::new(0, 4, 10).is_err());
+        assert!(MarkovChain::new(vec![vec![1.0, 1.0]], 0).is_err());
+        assert!(MarkovChain::new(vec![vec![0.0]], 0).is_err());
+        assert!(Mix::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_mix() {
+        let mut mix = Mix::new(vec![
+            (0.9, Box::new(PhasedWorkingSet::new(8, 8, 1000).unwrap())),
+            (0.1, Box::new(Scan::sequential(1000, 10_000))),
+        ])
+        .unwrap();
+        let references = generate_references(&mut mix, 10_000, &mut StdRng::seed_from_u64(3));
+        let scanned = references.iter().filter(|x| **x >= 1000).count();
+        assert!((800..1200).contains(&scanned));
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use super::{
+    translation::split_virtual_address, PagingSimulationResults, PagingSimulator, ReplacementPolicy,
+};
+use crate::error::Error;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AccessKind {
+    Instruction,
+    Load,
+    Store,
+    /// Load followed by a store to the same address
+    Modify,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// A single memory access read from a trace
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub address: u64,
+    /// Access size in bytes, 0 if the trace doesn't say
+    pub size: u64,
+}
+
+impl MemoryAccess {
+    /// Whether the access dirties the page
+    pub fn is_write(&self) -> bool {
+        matches!(self.kind, AccessKind::Store | AccessKind::Modify)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum MemoryTraceFormat {
+    /// Output of `valgrind --tool=lackey --trace-mem=yes`, e.g. ` L 04222cac,8`
+    Lackey,
+    /// One hex address per line, with or without the `0x` prefix,
+    /// optionally followed by `R` or `W`. Accesses are loads unless marked with `W`.
+    HexAddresses,
+}
+
+/// Streams memory accesses out of a trace, one line at a time,
+/// so that traces larger than memory can be simulated.
+/// Blank lines, `#` comments and Valgrind's `==pid==` messages are skipped.
+pub struct MemoryTraceReader<R: BufRead> {
+    reader: R,
+    format: MemoryTraceFormat,
+    line: String,
+    line_number: usize,
+    /// Mentioned in errors when known
+    path: Option<String>,
+}
+
+impl<R: BufRead> MemoryTraceReader<R> {
+    pub fn new(reader: R, format: MemoryTraceFormat) -> MemoryTraceReader<R> {
+        MemoryTraceReader {
+            reader,
+            format,
+            line: String::new(),
+            line_number: 0,
+            path: None,
+        }
+    }
+
+    fn error(&self, error: Error) -> Error {
+        match &self.path {
+            Some(path) => error.in_file(path),
+            None => error,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for MemoryTraceReader<R> {
+    type Item = Result<MemoryAccess, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(error) => return Some(Err(self.error(error.into()))),
+            }
+            let line = self.line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("==") {
+                continue;
+            }
+            let access = match self.format {
+                MemoryTraceFormat::Lackey => parse_lackey_line(line),
+                MemoryTraceFormat::HexAddresses => parse_hex_address_line(line),
+            };
+            return Some(access.ok_or_else(|| {
+                self.error(Error::parse(
+                    Some(self.line_number),
+                    format!("malformed access '{}'", line),
+                ))
+            }));
+        }
+    }
+}
+
+/// Opens a trace file for streaming
+pub fn open_memory_trace(
+    path: &str,
+    format: MemoryTraceFormat,
+) -> Result<MemoryTraceReader<BufReader<File>>, Error> {
+    let file = File::open(path).map_err(|error| Error::from(error).in_file(path))?;
+    let mut reader = MemoryTraceReader::new(BufReader::new(file), format);
+    reader.path = Some(path.to_owned());
+    Ok(reader)
+}
+
+/// Maps an access to the page holding its first byte, fails if the page size is 0
+pub fn page_of(access: &MemoryAccess, page_size: u64) -> Result<u64, Error> {
+    if page_size == 0 {
+        return Err(Error::configuration("page size is 0"));
+    }
+    Ok(split_virtual_address(access.address, page_size).0)
+}
+
+/// Results of running a memory trace through a replacement policy
+pub struct MemoryTraceResults {
+    /// Only hit and miss counts, history isn't kept for streamed traces
+    pub paging: PagingSimulationResults,
+    pub instructions: usize,
+    pub loads: usize,
+    /// Stores and modifies, i.e. accesses that dirty a page
+    pub stores: usize,
+}
+
+/// Runs a stream of accesses through a replacement policy without
+/// collecting the references first. Instruction fetches are skipped
+/// unless `include_instructions` is set.
+pub fn simulate_memory_trace<P, I>(
+    accesses: I,
+    physical_memory_size: usize,
+    page_size: u64,
+    include_instructions: bool,
+    policy: P,
+) -> Result<MemoryTraceResults, Error>
+where
+    P: ReplacementPolicy,
+    I: IntoIterator<Item = Result<MemoryAccess, Error>>,
+{
+    let mut simulator = PagingSimulator::new(physical_memory_size, policy)?.without_history();
+    let (mut instructions, mut loads, mut stores) = (0, 0, 0);
+    for access in accesses {
+        let access = access?;
+        match access.kind {
+            AccessKind::Instruction => {
+                instructions += 1;
+                if !include_instructions {
+                    continue;
+                }
+            }
+            AccessKind::Load => loads += 1,
+            AccessKind::Store | AccessKind::Modify => stores += 1,
+        }
+        simulator.access(page_of(&access, page_size)?, access.is_write());
+    }
+    Ok(MemoryTraceResults {
+        paging: simulator.results(),
+        instructions,
+        loads,
+        stores,
+    })
+}
+
+fn parse_lackey_line(line: &str) -> Option<MemoryAccess> {
+    let (kind, access) = line.split_once(char::is_whitespace)?;
+    let kind = match kind {
+        "I" => AccessKind::Instruction,
+        "L" => AccessKind::Load,
+        "S" => AccessKind::Store,
+        "M" => AccessKind::Modify,
+        _ => return None,
+    };
+    let (address, size) = access.trim().split_once(',')?;
+    Some(MemoryAccess {
+        kind,
+        address: u64::from_str_radix(address, 16).ok()?,
+        size: size.trim().parse().ok()?,
+    })
+}
+
+fn parse_hex_address_line(line: &str) -> Option<MemoryAccess> {
+    let mut tokens = line.split_whitespace();
+    let address = tokens.next()?;
+    let address = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    let kind = match tokens.next() {
+        None | Some("R") | Some("r") => AccessKind::Load,
+        Some("W") | Some("w") => AccessKind::Store,
+        Some(_) => return None,
+    };
+    Some(MemoryAccess {
+        kind,
+        address: u64::from_str_radix(address, 16).ok()?,
+        size: 0,
+    })
+}
+
+#[cfg(test)]
+mod memory_trace_tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::paging::LeastRecentlyUsed;
+
+    const LACKEY_TRACE: &str = "\
+==12345== Lackey, an example Valgrind tool
+I  04016a6,3
+ L 04222cac,8
+ S 04222cb0,4
+ M 0421f9e0,8
+I  04016a9,2
+";
+
+    fn read(trace: &str, format: MemoryTraceFormat) -> Vec<Result<MemoryAccess, Error>> {
+        MemoryTraceReader::new(Cursor::new(trace), format).collect()
+    }
+
+    #[test]
+    fn test_lackey_parsing() {
+        let accesses: Vec<MemoryAccess> = read(LACKEY_TRACE, MemoryTraceFormat::Lackey)
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        assert!(accesses.len() == 5);
+        assert!(
+            accesses[1]
+                == MemoryAccess {
+                    kind: AccessKind::Load,
+                    address: 0x04222cac,
+                    size: 8
+                }
+        );
+        let writes: Vec<bool> = accesses.iter().map(|x| x.is_write()).collect();
+        assert!(writes == vec![false, false, true, true, false]);
+        assert!(page_of(&accesses[3], 4096).unwrap() == 0x421f);
+        assert!(page_of(&accesses[3], 0).is_err());
+    }
+
+    #[test]
+    fn test_hex_address_parsing() {
+        let accesses = read(
+            "# addresses\n0x1000\n2fff W\n\n0X3000 r\n",
+            MemoryTraceFormat::HexAddresses,
+        );
+        let accesses: Vec<MemoryAccess> = accesses.into_iter().map(|x| x.unwrap()).collect();
+        assert!(accesses
+            .iter()
+            .map(|x| x.address)
+            .eq([0x1000, 0x2fff, 0x3000]));
+        assert!(accesses[1].kind == AccessKind::Store);
+
+        let malformed = read("0x1000\nnot an address\n", MemoryTraceFormat::HexAddresses);
+        let error = malformed[1].as_ref().unwrap_err();
+        assert!(matches!(error, Error::Parse { line: Some(2), .. }));
+        assert!(error.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn test_simulate_memory_trace() {
+        let accesses = MemoryTraceReader::new(Cursor::new(LACKEY_TRACE), MemoryTraceFormat::Lackey);
+        let results =
+            simulate_memory_trace(accesses, 4, 4096, false, LeastRecentlyUsed::default()).unwrap();
+        assert!((results.instructions, results.loads, results.stores) == (2, 1, 2));
+        // The load and store share a page
+        assert!(results.paging.misses == 2 && results.paging.hits == 1);
+        assert!(results.paging.references.is_empty());
+
+        let accesses = MemoryTraceReader::new(Cursor::new(LACKEY_TRACE), MemoryTraceFormat::Lackey);
+        let results =
+            simulate_memory_trace(accesses, 4, 4096, true, LeastRecentlyUsed::default()).unwrap();
+        assert!(results.paging.misses == 3 && results.paging.hits == 2);
+    }
+}
@@ -1,155 +1,363 @@
-use rand::seq::SliceRandom;
+use std::collections::{HashMap, VecDeque};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use rand_distr::{num_traits::pow, Distribution, Normal};
+use trace::PagingStep;
+
+use crate::error::Error;
+
+pub mod belady;
+pub mod generators;
+pub mod memory_trace;
+pub mod multiprocess;
+pub mod page_fault_frequency;
+pub mod programs;
+pub mod scan_resistant;
+pub mod stack_distance;
+pub mod trace;
+pub mod translation;
+pub mod working_set;
+pub mod write_back;
 
 pub struct PagingSimulationResults {
-    pub physical_memory_size: i16,
-    pub references: Vec<i16>,
-    pub hits: i16,
-    pub misses: i16,
+    pub physical_memory_size: usize,
+    pub references: Vec<u64>,
+    pub hits: usize,
+    pub misses: usize,
     pub hit_miss_ratio: f32,
+    /// Pages removed from memory to make room for other ones
+    pub evictions: usize,
+    /// Evictions of dirty pages, which had to be written back first
+    pub write_backs: usize,
     /// History of addresses that were replaced. None indicates a hit
-    pub swap_history: Vec<Option<i16>>,
+    pub swap_history: Vec<Option<usize>>,
+    /// Per-reference frame table trace, only recorded
+    /// if enabled with `PagingSimulator::with_trace`
+    pub trace: Vec<PagingStep>,
 }
 
-/// Least recently used scheduling algorithm implementation
-pub fn least_recently_used(
-    addresses_count: i16,
-    physical_memory_size: i16,
-    references: Vec<i16>,
-) -> PagingSimulationResults {
-    let references_copy = references.clone();
-    let mut physical_memory = vec![None; physical_memory_size as usize];
-    let mut hits = 0;
-    let mut misses = 0;
-    let mut used_indices: Vec<i16> = vec![];
-    let mut swap_history: Vec<Option<i16>> = vec![];
-
-    for reference in references {
-        // Denote a hit and frame usage
-        if physical_memory.contains(&Some(reference)) {
-            hits += 1;
-            let used_index = physical_memory
-                .iter()
-                .position(|x| x.is_some() && x.unwrap() == reference)
-                .unwrap();
-            used_indices.remove(used_index);
-            used_indices.push(used_index as i16);
-            swap_history.push(None);
-            continue;
+/// Page replacement policy driven by `PagingSimulator`.
+/// The simulator owns the frame table, so a policy only has to keep
+/// whatever bookkeeping it needs to pick a victim frame.
+/// Hooks are called once per reference, in reference order.
+pub trait ReplacementPolicy {
+    /// Called when the referenced page is already loaded into `frame`
+    fn on_hit(&mut self, _frame: usize, _page: u64) {}
+    /// Called after a missing page was loaded into `frame`
+    fn on_miss(&mut self, _frame: usize, _page: u64) {}
+    /// Picks the frame to be freed when there's no free memory left.
+    /// The returned frame is emptied right away, so the policy should
+    /// forget about the page that was in it. At least one frame is in use.
+    fn choose_victim(&mut self, frames: &[Option<u64>]) -> usize;
+}
+
+/// Simulates page references against a frame table,
+/// leaving replacement decisions to a `ReplacementPolicy`
+pub struct PagingSimulator<P: ReplacementPolicy> {
+    policy: P,
+    /// Frame table, None denotes a free frame
+    frames: Vec<Option<u64>>,
+    /// Page to frame index mapping of the frame table
+    resident_pages: HashMap<u64, usize>,
+    /// Whether the page in a given frame was written to since it was loaded
+    dirty: Vec<bool>,
+    /// Number of frames that can be used at the moment
+    frame_limit: usize,
+    references: Vec<u64>,
+    hits: usize,
+    misses: usize,
+    evictions: usize,
+    write_backs: usize,
+    swap_history: Vec<Option<usize>>,
+    trace: Option<Vec<PagingStep>>,
+    /// Whether references and swap history are kept for the results
+    record_history: bool,
+}
+
+impl<P: ReplacementPolicy> PagingSimulator<P> {
+    /// Fails without frames, as policies can't pick a victim out of none
+    pub fn new(physical_memory_size: usize, policy: P) -> Result<PagingSimulator<P>, Error> {
+        if physical_memory_size == 0 {
+            return Err(Error::configuration(
+                "paging simulations need at least one frame",
+            ));
+        }
+        Ok(PagingSimulator {
+            policy,
+            frames: vec![None; physical_memory_size],
+            resident_pages: HashMap::new(),
+            dirty: vec![false; physical_memory_size],
+            frame_limit: physical_memory_size,
+            references: vec![],
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            write_backs: 0,
+            swap_history: vec![],
+            trace: None,
+            record_history: true,
+        })
+    }
+
+    /// Enables recording a frame table snapshot after every reference
+    pub fn with_trace(mut self) -> PagingSimulator<P> {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    /// Stops keeping references and swap history, so that long
+    /// reference streams can be simulated in constant memory.
+    /// Results only have hit and miss counts then.
+    pub fn without_history(mut self) -> PagingSimulator<P> {
+        self.record_history = false;
+        self
+    }
+
+    /// Simulates a single page read, returns true on a hit
+    pub fn reference(&mut self, page: u64) -> bool {
+        self.access(page, false)
+    }
+
+    /// Simulates a single page read or write, returns true on a hit.
+    /// Written pages are marked dirty and have to be written back when evicted.
+    pub fn access(&mut self, page: u64, write: bool) -> bool {
+        if self.record_history {
+            self.references.push(page);
+        }
+        if let Some(frame) = self.resident_pages.get(&page).copied() {
+            self.hits += 1;
+            self.dirty[frame] |= write;
+            if self.record_history {
+                self.swap_history.push(None);
+            }
+            self.policy.on_hit(frame, page);
+            self.record_step(page, None, None);
+            return true;
         }
-        misses += 1;
-        if physical_memory.contains(&None) {
+        self.misses += 1;
+        let (frame, evicted_page) = if self.resident_pages.len() < self.frame_limit {
             // Allocate free memory
-            let free_index = physical_memory
-                .iter()
-                .position(|index| index.is_none())
-                .unwrap();
-            physical_memory[free_index] = Some(reference);
-            used_indices.push(free_index as i16);
+            (self.frames.iter().position(|x| x.is_none()).unwrap(), None)
         } else {
-            // Replace least recently used frame
-            let least_recently_used_index = used_indices[0];
-            used_indices.remove(0);
-            used_indices.push(least_recently_used_index);
-            swap_history.push(Some(least_recently_used_index));
-            physical_memory[least_recently_used_index as usize] = Some(reference);
+            let (victim, evicted_page) = self.evict();
+            if self.record_history {
+                self.swap_history.push(Some(victim));
+            }
+            (victim, Some(evicted_page))
+        };
+        self.frames[frame] = Some(page);
+        self.dirty[frame] = write;
+        self.resident_pages.insert(page, frame);
+        self.policy.on_miss(frame, page);
+        self.record_step(page, Some(frame), evicted_page);
+        false
+    }
+
+    /// Changes the number of frames available to the simulation.
+    /// Shrinking evicts pages chosen by the replacement policy.
+    /// Fails without frames, same as `PagingSimulator::new`.
+    pub fn set_frame_limit(&mut self, frame_limit: usize) -> Result<(), Error> {
+        if frame_limit == 0 {
+            return Err(Error::configuration(
+                "paging simulations need at least one frame",
+            ));
+        }
+        if frame_limit > self.frames.len() {
+            self.frames.resize(frame_limit, None);
+            self.dirty.resize(frame_limit, false);
         }
+        self.frame_limit = frame_limit;
+        while self.resident_pages.len() > self.frame_limit {
+            self.evict();
+        }
+        Ok(())
     }
-    let hit_miss_ratio: f32 = hits as f32 / misses as f32;
-    PagingSimulationResults {
-        physical_memory_size,
-        references: references_copy,
-        hit_miss_ratio,
-        swap_history,
-        hits,
-        misses,
+
+    pub fn frame_limit(&self) -> usize {
+        self.frame_limit
     }
-}
 
-/// FIFO algorithm implementation
-pub fn fifo(
-    addresses_count: i16,
-    physical_memory_size: i16,
-    references: Vec<i16>,
-) -> PagingSimulationResults {
-    let references_copy = references.clone();
-    let mut physical_memory = vec![None; physical_memory_size as usize];
-    let mut hits = 0;
-    let mut misses = 0;
-    let mut used_indices: Vec<i16> = vec![];
-    let mut swap_history: Vec<Option<i16>> = vec![];
-    for reference in references {
-        if physical_memory.contains(&Some(reference)) {
-            // Denote a hit
-            hits += 1;
-            continue;
+    pub fn contains(&self, page: u64) -> bool {
+        self.resident_pages.contains_key(&page)
+    }
+
+    pub fn frames(&self) -> &[Option<u64>] {
+        &self.frames
+    }
+
+    pub fn run(mut self, references: Vec<u64>) -> PagingSimulationResults {
+        for reference in references {
+            self.reference(reference);
         }
-        misses += 1;
-        if physical_memory.contains(&None) {
-            // Allocate free memory
-            let free_index = physical_memory
-                .iter()
-                .position(|index| index.is_none())
-                .unwrap();
-            physical_memory[free_index] = Some(reference);
-            used_indices.push(free_index as i16);
-        } else {
-            // Replace frame that was least recently changed
-            let first_in_index = used_indices[0];
-            used_indices.remove(0);
-            used_indices.push(first_in_index);
-            swap_history.push(Some(first_in_index));
-            physical_memory[first_in_index as usize] = Some(reference);
+        self.results()
+    }
+
+    pub fn results(self) -> PagingSimulationResults {
+        let hit_miss_ratio: f32 = self.hits as f32 / self.misses as f32;
+        PagingSimulationResults {
+            physical_memory_size: self.frames.len(),
+            references: self.references,
+            hit_miss_ratio,
+            swap_history: self.swap_history,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            write_backs: self.write_backs,
+            trace: self.trace.unwrap_or_default(),
         }
     }
-    let hit_miss_ratio: f32 = hits as f32 / misses as f32;
-    PagingSimulationResults {
-        physical_memory_size,
-        references: references_copy,
-        hit_miss_ratio,
-        swap_history,
-        hits,
-        misses,
+
+    /// Returns the victim frame and the page it held
+    fn evict(&mut self) -> (usize, u64) {
+        let victim = self.policy.choose_victim(&self.frames);
+        let evicted_page = self.frames[victim].take().unwrap();
+        self.evictions += 1;
+        if self.dirty[victim] {
+            self.write_backs += 1;
+            self.dirty[victim] = false;
+        }
+        self.resident_pages.remove(&evicted_page);
+        (victim, evicted_page)
     }
+
+    fn record_step(&mut self, page: u64, victim_frame: Option<usize>, evicted_page: Option<u64>) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(PagingStep {
+                reference: page,
+                hit: victim_frame.is_none(),
+                victim_frame,
+                evicted_page,
+                frames: self.frames.clone(),
+            });
+        }
+    }
+}
+
+#[derive(Default)]
+/// Least recently used replacement policy
+pub struct LeastRecentlyUsed {
+    /// Frame indices, the front being the least recently used one
+    used_indices: VecDeque<usize>,
+}
+
+impl ReplacementPolicy for LeastRecentlyUsed {
+    fn on_hit(&mut self, frame: usize, _page: u64) {
+        self.used_indices.retain(|x| *x != frame);
+        self.used_indices.push_back(frame);
+    }
+
+    fn on_miss(&mut self, frame: usize, _page: u64) {
+        self.used_indices.push_back(frame);
+    }
+
+    fn choose_victim(&mut self, _frames: &[Option<u64>]) -> usize {
+        self.used_indices.pop_front().unwrap()
+    }
+}
+
+#[derive(Default)]
+/// FIFO replacement policy
+pub struct Fifo {
+    /// Frame indices in the order they were loaded
+    loaded_indices: VecDeque<usize>,
+}
+
+impl ReplacementPolicy for Fifo {
+    fn on_miss(&mut self, frame: usize, _page: u64) {
+        self.loaded_indices.push_back(frame);
+    }
+
+    fn choose_victim(&mut self, _frames: &[Option<u64>]) -> usize {
+        // Replace frame that was least recently changed
+        self.loaded_indices.pop_front().unwrap()
+    }
+}
+
+/// Least recently used scheduling algorithm implementation
+pub fn least_recently_used(
+    physical_memory_size: usize,
+    references: Vec<u64>,
+) -> Result<PagingSimulationResults, Error> {
+    Ok(PagingSimulator::new(physical_memory_size, LeastRecentlyUsed::default())?.run(references))
+}
+
+/// FIFO algorithm implementation
+pub fn fifo(
+    physical_memory_size: usize,
+    references: Vec<u64>,
+) -> Result<PagingSimulationResults, Error> {
+    Ok(PagingSimulator::new(physical_memory_size, Fifo::default())?.run(references))
 }
 
 /// Returns a randomly generated list of integers representing
 /// references to particular memory addresses.
 /// Can be adjusted to meet particular standard deviation requirements
 pub fn generate_normal_distribution_of_references(
-    addresses_count: i16,
+    addresses_count: u64,
     mean_references_count: f32,
     standard_deviation: f32,
-) -> Vec<i16> {
-    let mut references: Vec<i16> = vec![];
-    let mut references_counts: Vec<i16> = vec![];
-    let mut rng = rand::rng();
-    let normal = Normal::new(mean_references_count, standard_deviation).unwrap();
+    rng: &mut StdRng,
+) -> Result<Vec<u64>, Error> {
+    let mut references: Vec<u64> = vec![];
+    let mut references_counts: Vec<usize> = vec![];
+    let normal = Normal::new(mean_references_count, standard_deviation)
+        .ok()
+        .filter(|_| standard_deviation >= 0.0)
+        .ok_or_else(|| {
+            Error::configuration(format!(
+                "invalid references count standard deviation {}",
+                standard_deviation
+            ))
+        })?;
 
     for _ in 0..addresses_count {
-        let sample = normal.sample(&mut rng);
-        let count = sample.max(0.0) as i16;
+        let sample = normal.sample(rng);
+        let count = sample.max(0.0) as usize;
         references_counts.push(count);
     }
 
-    let current_mean = references_counts.iter().sum::<i16>() as f32 / addresses_count as f32;
+    let current_mean = references_counts.iter().sum::<usize>() as f32 / addresses_count as f32;
     let current_standard_deviation = calculate_standard_deviation(&references_counts, current_mean);
 
-    for i in 0..references_counts.len() {
-        let difference = references_counts[i] as f32 - current_mean;
+    for (i, references_count) in references_counts.iter().enumerate() {
+        let difference = *references_count as f32 - current_mean;
         let normalized_references_count =
             mean_references_count + (standard_deviation / current_standard_deviation) * difference;
         for _ in 0..normalized_references_count.round() as usize {
-            references.push(i as i16)
+            references.push(i as u64)
         }
     }
 
-    references.shuffle(&mut rng);
+    references.shuffle(rng);
+
+    Ok(references)
+}
+
+/// Returns a list of references to a small, frequently used set of pages
+/// (addresses `0..hot_set_size`) with a one-off sequential scan of
+/// `scan_length` other pages mixed in from the first quarter onwards.
+/// That's the pattern least recently used handles particularly poorly.
+pub fn generate_hot_set_with_scan(
+    hot_set_size: u64,
+    hot_references_count: usize,
+    scan_length: u64,
+    rng: &mut StdRng,
+) -> Vec<u64> {
+    let mut references: Vec<u64> = vec![];
+    let mut next_scanned_address = hot_set_size;
+
+    for i in 0..hot_references_count {
+        references.push(rng.random_range(0..hot_set_size));
+        if i >= hot_references_count / 4 && next_scanned_address < hot_set_size + scan_length {
+            references.push(next_scanned_address);
+            next_scanned_address += 1;
+        }
+    }
 
     references
 }
 
-fn calculate_standard_deviation(array: &Vec<i16>, mean: f32) -> f32 {
+fn calculate_standard_deviation(array: &Vec<usize>, mean: f32) -> f32 {
     let mut sum = 0.0;
     for element in array {
         sum += pow(*element as f32 - mean, 2);
@@ -159,18 +367,62 @@ fn calculate_standard_deviation(array: &Vec<i16>, mean: f32) -> f32 {
 
 #[cfg(test)]
 mod round_robin_tests {
+    use rand::SeedableRng;
+
     use super::*;
     #[test]
     fn test_fifo() {
         let references = vec![0, 1, 2, 3, 4, 5, 6, 7, 4, 5, 6, 7];
-        let results = fifo(8, 4, references);
+        let results = fifo(4, references).unwrap();
         assert!(results.hit_miss_ratio == 4.0 / 8.0)
     }
 
+    #[test]
+    fn test_no_frames() {
+        let error = fifo(0, vec![0, 1]).err().unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+        assert!(least_recently_used(0, vec![0, 1]).is_err());
+        let mut simulator = PagingSimulator::new(2, Fifo::default()).unwrap();
+        assert!(simulator.set_frame_limit(0).is_err());
+    }
+
     #[test]
     fn test_least_recently_used() {
         let references = vec![0, 1, 2, 0, 5, 0];
-        let results = fifo(8, 4, references);
+        let results = fifo(4, references).unwrap();
         assert!(results.hit_miss_ratio == 2.0 / 4.0)
     }
+
+    /// Always evicts the page in the last frame
+    struct LastFrame;
+
+    impl ReplacementPolicy for LastFrame {
+        fn choose_victim(&mut self, frames: &[Option<u64>]) -> usize {
+            frames.len() - 1
+        }
+    }
+
+    #[test]
+    fn test_custom_replacement_policy() {
+        let references = vec![0, 1, 2, 3, 0, 2];
+        let results = PagingSimulator::new(2, LastFrame).unwrap().run(references);
+        assert!(results.misses == 5);
+        assert!(results.swap_history == vec![Some(1), Some(1), None, Some(1)]);
+    }
+
+    #[test]
+    fn test_invalid_standard_deviation() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let references = generate_normal_distribution_of_references(8, 4.0, -1.0, &mut rng);
+        assert!(matches!(references, Err(Error::Configuration(_))));
+    }
+
+    #[test]
+    fn test_long_reference_string_with_large_page_numbers() {
+        // Way beyond what fits into 16 bit counters and page numbers
+        let references: Vec<u64> = (0..100_000).map(|x| (x % 4) << 40).collect();
+        let results = least_recently_used(4, references).unwrap();
+        assert!(results.misses == 4);
+        assert!(results.hits == 99_996);
+    }
 }
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{PagingSimulator, ReplacementPolicy};
+use crate::error::Error;
+
+/// A process taking part in a multi-process paging simulation,
+/// page numbers are local to the process
+pub struct PagingProcess {
+    pub name: String,
+    pub references: Vec<u64>,
+}
+
+#[derive(Clone, Copy)]
+/// How frames are split between processes under local replacement
+pub enum FrameAllocation {
+    /// Every process gets the same number of frames
+    Equal,
+    /// Frames are split proportionally to the number
+    /// of distinct pages a process references
+    Proportional,
+}
+
+#[derive(Clone, Copy)]
+pub enum ReplacementScope {
+    /// A process can only replace its own pages, within its frame allocation
+    Local,
+    /// A process can replace any page, stealing frames from other processes
+    Global,
+}
+
+#[derive(Clone, Copy)]
+/// Order in which reference streams of different processes are interleaved
+pub enum InterleavingSchedule {
+    /// Processes take turns, making up to `quantum` references each
+    RoundRobin { quantum: usize },
+    /// Every reference is made by a uniformly chosen process,
+    /// picked with a generator seeded with `seed`
+    Random { seed: u64 },
+}
+
+pub struct MultiprocessPagingConfig {
+    pub physical_memory_size: usize,
+    /// Only used with local replacement
+    pub allocation: FrameAllocation,
+    pub scope: ReplacementScope,
+    pub schedule: InterleavingSchedule,
+}
+
+/// Paging statistics of a single process
+pub struct ProcessPagingResults {
+    pub name: String,
+    pub references_count: usize,
+    pub hits: usize,
+    pub misses: usize,
+    pub fault_rate: f32,
+    /// Frames allocated under local replacement, None under global replacement
+    pub allocated_frames: Option<usize>,
+    /// Number of frames holding the process' pages after every
+    /// reference made by any process
+    pub frames_held_history: Vec<usize>,
+}
+
+/// Results of a multi-process paging simulation
+pub struct MultiprocessPagingResults {
+    pub processes: Vec<ProcessPagingResults>,
+    /// Index of the process making each reference
+    pub interleaving: Vec<usize>,
+    pub total_misses: usize,
+}
+
+/// Simulates several processes sharing physical memory.
+/// `policy` creates a replacement policy for a given number of frames:
+/// one per process under local replacement, a single one under global replacement.
+/// Fails without frames, local replacement also if there are more processes than frames.
+pub fn simulate_multiprocess_paging<P, F>(
+    processes: Vec<PagingProcess>,
+    config: MultiprocessPagingConfig,
+    policy: F,
+) -> Result<MultiprocessPagingResults, Error>
+where
+    P: ReplacementPolicy,
+    F: Fn(usize) -> P,
+{
+    if config.physical_memory_size == 0 {
+        return Err(Error::configuration(
+            "processes can't share physical memory without frames",
+        ));
+    }
+    let interleaving = interleave(&processes, config.schedule);
+    let mut hits: Vec<usize> = vec![0; processes.len()];
+    let mut misses: Vec<usize> = vec![0; processes.len()];
+    let mut frames_held_history: Vec<Vec<usize>> = vec![vec![]; processes.len()];
+    let mut next_reference: Vec<usize> = vec![0; processes.len()];
+    let mut allocations: Vec<Option<usize>> = vec![None; processes.len()];
+
+    let mut record = |process: usize, hit: bool, frames_held: Vec<usize>| {
+        match hit {
+            true => hits[process] += 1,
+            false => misses[process] += 1,
+        }
+        for (history, held) in frames_held_history.iter_mut().zip(frames_held) {
+            history.push(held);
+        }
+    };
+
+    match config.scope {
+        ReplacementScope::Local => {
+            let allocation =
+                allocate_frames(&processes, config.physical_memory_size, config.allocation)?;
+            let mut simulators: Vec<PagingSimulator<P>> = allocation
+                .iter()
+                .map(|frames| PagingSimulator::new(*frames, policy(*frames)))
+                .collect::<Result<_, _>>()?;
+            for process in &interleaving {
+                let page = processes[*process].references[next_reference[*process]];
+                next_reference[*process] += 1;
+                let hit = simulators[*process].reference(page);
+                let frames_held = simulators
+                    .iter()
+                    .map(|simulator| simulator.frames().iter().flatten().count())
+                    .collect();
+                record(*process, hit, frames_held);
+            }
+            allocations = allocation.into_iter().map(Some).collect();
+        }
+        ReplacementScope::Global => {
+            let mut simulator = PagingSimulator::new(
+                config.physical_memory_size,
+                policy(config.physical_memory_size),
+            )?;
+            // Every (process, page) pair gets its own identifier in the shared frame table
+            let mut global_pages: HashMap<(usize, u64), u64> = HashMap::new();
+            let mut page_owners: Vec<usize> = vec![];
+            for process in &interleaving {
+                let page = processes[*process].references[next_reference[*process]];
+                next_reference[*process] += 1;
+                let global_page = *global_pages.entry((*process, page)).or_insert_with(|| {
+                    page_owners.push(*process);
+                    (page_owners.len() - 1) as u64
+                });
+                let hit = simulator.reference(global_page);
+                let mut frames_held: Vec<usize> = vec![0; processes.len()];
+                for page in simulator.frames().iter().flatten() {
+                    frames_held[page_owners[*page as usize]] += 1;
+                }
+                record(*process, hit, frames_held);
+            }
+        }
+    }
+
+    let total_misses = misses.iter().sum();
+    let processes = processes
+        .into_iter()
+        .enumerate()
+        .zip(frames_held_history)
+        .map(|((i, process), frames_held_history)| ProcessPagingResults {
+            name: process.name,
+            references_count: process.references.len(),
+            hits: hits[i],
+            misses: misses[i],
+            fault_rate: misses[i] as f32 / process.references.len().max(1) as f32,
+            allocated_frames: allocations[i],
+            frames_held_history,
+        })
+        .collect();
+    Ok(MultiprocessPagingResults {
+        processes,
+        interleaving,
+        total_misses,
+    })
+}
+
+/// Splits physical memory between processes, every process gets
+/// at least one frame and leftovers go to the first processes.
+/// Fails if there are more processes than frames.
+pub fn allocate_frames(
+    processes: &[PagingProcess],
+    physical_memory_size: usize,
+    allocation: FrameAllocation,
+) -> Result<Vec<usize>, Error> {
+    if processes.len() > physical_memory_size {
+        return Err(Error::configuration(format!(
+            "{} processes can't get a frame each out of {}",
+            processes.len(),
+            physical_memory_size
+        )));
+    }
+    let sizes: Vec<usize> = match allocation {
+        FrameAllocation::Equal => vec![1; processes.len()],
+        FrameAllocation::Proportional => processes
+            .iter()
+            .map(|process| {
+                let mut pages = process.references.clone();
+                pages.sort();
+                pages.dedup();
+                pages.len().max(1)
+            })
+            .collect(),
+    };
+    let total_size: usize = sizes.iter().sum();
+    let mut frames: Vec<usize> = sizes
+        .iter()
+        .map(|size| ((size * physical_memory_size) / total_size).max(1))
+        .collect();
+    // Small processes rounded up to one frame are paid for by the largest ones
+    while frames.iter().sum::<usize>() > physical_memory_size {
+        let largest = (0..frames.len()).max_by_key(|i| (frames[*i], usize::MAX - i));
+        frames[largest.unwrap()] -= 1;
+    }
+    let mut leftover = physical_memory_size - frames.iter().sum::<usize>();
+    for allocated in frames.iter_mut() {
+        if leftover == 0 {
+            break;
+        }
+        *allocated += 1;
+        leftover -= 1;
+    }
+    Ok(frames)
+}
+
+/// Returns indices of processes in the order they make their references
+fn interleave(processes: &[PagingProcess], schedule: InterleavingSchedule) -> Vec<usize> {
+    let mut remaining: Vec<usize> = processes.iter().map(|x| x.references.len()).collect();
+    let mut interleaving: Vec<usize> = vec![];
+    match schedule {
+        InterleavingSchedule::RoundRobin { quantum } => {
+            while remaining.iter().any(|x| *x > 0) {
+                for (process, left) in remaining.iter_mut().enumerate() {
+                    let turn = quantum.max(1).min(*left);
+                    interleaving.extend(std::iter::repeat_n(process, turn));
+                    *left -= turn;
+                }
+            }
+        }
+        InterleavingSchedule::Random { seed } => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            loop {
+                let ready: Vec<usize> =
+                    (0..remaining.len()).filter(|i| remaining[*i] > 0).collect();
+                if ready.is_empty() {
+                    break;
+                }
+                let process = ready[rng.random_range(0..ready.len())];
+                remaining[process] -= 1;
+                interleaving.push(process);
+            }
+        }
+    }
+    interleaving
+}
+
+#[cfg(test)]
+mod multiprocess_tests {
+    use super::*;
+    use crate::paging::LeastRecentlyUsed;
+
+    fn get_processes() -> Vec<PagingProcess> {
+        vec![
+            PagingProcess {
+                name: "Small".to_owned(),
+                references: [0, 1].repeat(5),
+            },
+            PagingProcess {
+                name: "Hungry".to_owned(),
+                references: (0..10).collect(),
+            },
+        ]
+    }
+
+    fn get_config(scope: ReplacementScope) -> MultiprocessPagingConfig {
+        MultiprocessPagingConfig {
+            physical_memory_size: 3,
+            allocation: FrameAllocation::Equal,
+            scope,
+            schedule: InterleavingSchedule::RoundRobin { quantum: 2 },
+        }
+    }
+
+    #[test]
+    fn test_local_replacement_protects_small_process() {
+        let results = simulate_multiprocess_paging(
+            get_processes(),
+            get_config(ReplacementScope::Local),
+            |_| LeastRecentlyUsed::default(),
+        )
+        .unwrap();
+        assert!(results.processes[0].allocated_frames == Some(2));
+        assert!(results.processes[0].misses == 2);
+        assert!(results.processes[1].misses == 10);
+    }
+
+    #[test]
+    fn test_global_replacement_steals_frames() {
+        let results = simulate_multiprocess_paging(
+            get_processes(),
+            get_config(ReplacementScope::Global),
+            |_| LeastRecentlyUsed::default(),
+        )
+        .unwrap();
+        assert!(results.processes[0].misses == 10);
+        assert!(results.total_misses == 20);
+
+        for scope in [ReplacementScope::Global, ReplacementScope::Local] {
+            let config = MultiprocessPagingConfig {
+                physical_memory_size: 0,
+                ..get_config(scope)
+            };
+            let results = simulate_multiprocess_paging(get_processes(), config, |_| {
+                LeastRecentlyUsed::default()
+            });
+            assert!(matches!(results, Err(Error::Configuration(_))));
+        }
+    }
+
+    #[test]
+    fn test_proportional_allocation() {
+        let frames = allocate_frames(&get_processes(), 12, FrameAllocation::Proportional).unwrap();
+        assert!(frames == vec![2, 10]);
+
+        // Small processes are rounded up to a frame, the total still fits
+        let processes = vec![
+            PagingProcess {
+                name: "Tiny".to_owned(),
+                references: vec![0],
+            },
+            PagingProcess {
+                name: "Tiny too".to_owned(),
+                references: vec![0],
+            },
+            PagingProcess {
+                name: "Large".to_owned(),
+                references: (0..100).collect(),
+            },
+        ];
+        let frames = allocate_frames(&processes, 3, FrameAllocation::Proportional).unwrap();
+        assert!(frames == vec![1, 1, 1]);
+        assert!(allocate_frames(&get_processes(), 1, FrameAllocation::Equal).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use super::{LeastRecentlyUsed, PagingSimulationResults, PagingSimulator};
+use crate::error::Error;
+
+/// Parameters of the page fault frequency frame allocation controller
+pub struct PageFaultFrequencyConfig {
+    pub initial_frames: usize,
+    pub min_frames: usize,
+    pub max_frames: usize,
+    /// Number of most recent references the fault rate is measured over
+    pub measurement_window: usize,
+    /// Fault rate below which a frame is taken away
+    pub lower_fault_rate: f32,
+    /// Fault rate above which another frame is granted
+    pub upper_fault_rate: f32,
+}
+
+impl PageFaultFrequencyConfig {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.min_frames == 0 || self.min_frames > self.max_frames {
+            return Err(Error::configuration(format!(
+                "frame bounds [{}, {}] don't leave at least one frame",
+                self.min_frames, self.max_frames
+            )));
+        }
+        if self.measurement_window == 0 {
+            return Err(Error::configuration("measurement window is empty"));
+        }
+        Ok(())
+    }
+}
+
+/// Results of a page fault frequency simulation
+pub struct PageFaultFrequencyResults {
+    pub paging: PagingSimulationResults,
+    /// Number of frames allocated right after each reference
+    pub frame_allocation_history: Vec<usize>,
+    /// Fault rate measured right after each reference
+    pub fault_rate_history: Vec<f32>,
+}
+
+/// Page fault frequency (PFF) simulation.
+/// Pages are replaced with least recently used, but the number of frames
+/// is not fixed: every page fault the controller looks at the fault rate
+/// measured over the last `measurement_window` references and grants
+/// another frame if it's above `upper_fault_rate` or takes one away
+/// (evicting the least recently used page if necessary)
+/// if it's below `lower_fault_rate`.
+pub fn page_fault_frequency(
+    references: Vec<u64>,
+    config: PageFaultFrequencyConfig,
+) -> Result<PageFaultFrequencyResults, Error> {
+    config.validate()?;
+    let mut simulator = PagingSimulator::new(config.max_frames, LeastRecentlyUsed::default())?;
+    let mut allocated_frames = config
+        .initial_frames
+        .clamp(config.min_frames, config.max_frames);
+    simulator.set_frame_limit(allocated_frames)?;
+    let mut frame_allocation_history: Vec<usize> = vec![];
+    let mut fault_rate_history: Vec<f32> = vec![];
+    let mut recent_faults: VecDeque<bool> = VecDeque::new();
+
+    for reference in references {
+        let fault = !simulator.contains(reference);
+        recent_faults.push_back(fault);
+        if recent_faults.len() > config.measurement_window {
+            recent_faults.pop_front();
+        }
+        let fault_rate =
+            recent_faults.iter().filter(|x| **x).count() as f32 / recent_faults.len() as f32;
+        fault_rate_history.push(fault_rate);
+
+        if fault {
+            if fault_rate > config.upper_fault_rate && allocated_frames < config.max_frames {
+                allocated_frames += 1;
+            } else if fault_rate < config.lower_fault_rate && allocated_frames > config.min_frames {
+                allocated_frames -= 1;
+            }
+            // Taking a frame away gives the least recently used one back if it's in use
+            simulator.set_frame_limit(allocated_frames)?;
+        }
+        simulator.reference(reference);
+        frame_allocation_history.push(allocated_frames);
+    }
+    Ok(PageFaultFrequencyResults {
+        paging: simulator.results(),
+        frame_allocation_history,
+        fault_rate_history,
+    })
+}
+
+#[cfg(test)]
+mod page_fault_frequency_tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_granted_on_high_fault_rate() {
+        let references: Vec<u64> = (0..10).collect();
+        let config = PageFaultFrequencyConfig {
+            initial_frames: 2,
+            min_frames: 1,
+            max_frames: 5,
+            measurement_window: 4,
+            lower_fault_rate: 0.2,
+            upper_fault_rate: 0.5,
+        };
+        let results = page_fault_frequency(references, config).unwrap();
+        assert!(results.frame_allocation_history[..4] == [3, 4, 5, 5]);
+        assert!(results.paging.misses == 10);
+    }
+
+    #[test]
+    fn test_frames_released_on_low_fault_rate() {
+        let references = vec![0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2];
+        let config = PageFaultFrequencyConfig {
+            initial_frames: 4,
+            min_frames: 1,
+            max_frames: 4,
+            measurement_window: 4,
+            lower_fault_rate: 0.3,
+            upper_fault_rate: 0.9,
+        };
+        let results = page_fault_frequency(references, config).unwrap();
+        assert!(*results.frame_allocation_history.last().unwrap() == 2);
+        // With just two frames left, page 0 has to go
+        assert!(*results.paging.swap_history.last().unwrap() == Some(0));
+    }
+
+    #[test]
+    fn test_invalid_frame_bounds() {
+        let config = PageFaultFrequencyConfig {
+            initial_frames: 2,
+            min_frames: 4,
+            max_frames: 3,
+            measurement_window: 4,
+            lower_fault_rate: 0.2,
+            upper_fault_rate: 0.5,
+        };
+        let error = page_fault_frequency(vec![0, 1], config).err().unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+    }
+}
@@ -0,0 +1,253 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::translation::split_virtual_address;
+use crate::error::Error;
+
+#[derive(Clone, Copy)]
+/// Virtual memory layout of the simulated programs
+pub struct MemoryLayout {
+    /// Page size in bytes
+    pub page_size: u64,
+    /// Size of a single array element or list node in bytes
+    pub element_size: u64,
+}
+
+/// Byte addresses accessed by a simulated program, in access order
+pub struct ProgramTrace {
+    pub layout: MemoryLayout,
+    pub addresses: Vec<u64>,
+    /// Next free address, every allocation starts at a page boundary
+    next_free: u64,
+}
+
+impl ProgramTrace {
+    /// Fails if the page size is 0
+    pub fn new(layout: MemoryLayout) -> Result<ProgramTrace, Error> {
+        if layout.page_size == 0 {
+            return Err(Error::configuration("page size is 0"));
+        }
+        Ok(ProgramTrace {
+            layout,
+            addresses: vec![],
+            next_free: 0,
+        })
+    }
+
+    /// Reserves space for `elements_count` elements and returns the base address
+    pub fn allocate(&mut self, elements_count: u64) -> u64 {
+        let base = self.next_free;
+        let size = elements_count * self.layout.element_size;
+        self.next_free = (base + size).div_ceil(self.layout.page_size) * self.layout.page_size;
+        base
+    }
+
+    /// Records an access to the element `index` of the array starting at `base`
+    pub fn access(&mut self, base: u64, index: u64) {
+        self.addresses.push(base + index * self.layout.element_size);
+    }
+
+    /// Page numbers of the accessed addresses, ready to be passed to a replacement policy
+    pub fn page_references(&self) -> Vec<u64> {
+        self.addresses
+            .iter()
+            .map(|address| split_virtual_address(*address, self.layout.page_size).0)
+            .collect()
+    }
+}
+
+/// Reads every element of a `rows` x `columns` row-major array, row by row
+pub fn row_major_traversal(
+    rows: u64,
+    columns: u64,
+    layout: MemoryLayout,
+) -> Result<ProgramTrace, Error> {
+    let mut trace = ProgramTrace::new(layout)?;
+    let array = trace.allocate(rows * columns);
+    for row in 0..rows {
+        for column in 0..columns {
+            trace.access(array, row * columns + column);
+        }
+    }
+    Ok(trace)
+}
+
+/// Reads every element of a `rows` x `columns` row-major array, column by column
+pub fn column_major_traversal(
+    rows: u64,
+    columns: u64,
+    layout: MemoryLayout,
+) -> Result<ProgramTrace, Error> {
+    let mut trace = ProgramTrace::new(layout)?;
+    let array = trace.allocate(rows * columns);
+    for column in 0..columns {
+        for row in 0..rows {
+            trace.access(array, row * columns + column);
+        }
+    }
+    Ok(trace)
+}
+
+/// C = A * B for `size` x `size` row-major matrices using the i-j-k loop order.
+/// Every step of the innermost loop reads A\[i\]\[k\] and B\[k\]\[j\] and updates C\[i\]\[j\].
+pub fn matrix_multiply(size: u64, layout: MemoryLayout) -> Result<ProgramTrace, Error> {
+    blocked_matrix_multiply(size, size, layout)
+}
+
+/// Same as `matrix_multiply`, but working on `block_size` x `block_size` tiles,
+/// so that the tiles being multiplied can stay in memory
+pub fn blocked_matrix_multiply(
+    size: u64,
+    block_size: u64,
+    layout: MemoryLayout,
+) -> Result<ProgramTrace, Error> {
+    let mut trace = ProgramTrace::new(layout)?;
+    let a = trace.allocate(size * size);
+    let b = trace.allocate(size * size);
+    let c = trace.allocate(size * size);
+    let block_size = block_size.clamp(1, size.max(1)) as usize;
+    for i_block in (0..size).step_by(block_size) {
+        for j_block in (0..size).step_by(block_size) {
+            for k_block in (0..size).step_by(block_size) {
+                for i in i_block..(i_block + block_size as u64).min(size) {
+                    for j in j_block..(j_block + block_size as u64).min(size) {
+                        for k in k_block..(k_block + block_size as u64).min(size) {
+                            trace.access(a, i * size + k);
+                            trace.access(b, k * size + j);
+                            trace.access(c, i * size + j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(trace)
+}
+
+/// Walks a linked list of `nodes_count` nodes from head to tail.
+/// Nodes are scattered over a pool of memory in a random order,
+/// as they would be after many allocations and frees.
+pub fn linked_list_walk(
+    nodes_count: u64,
+    layout: MemoryLayout,
+    rng: &mut StdRng,
+) -> Result<ProgramTrace, Error> {
+    let mut trace = ProgramTrace::new(layout)?;
+    let pool = trace.allocate(nodes_count);
+    let mut slots: Vec<u64> = (0..nodes_count).collect();
+    slots.shuffle(rng);
+    for slot in slots {
+        trace.access(pool, slot);
+    }
+    Ok(trace)
+}
+
+/// Looks up `searches_count` random keys in a sorted array of `elements_count`
+/// elements using binary search
+pub fn binary_search(
+    elements_count: u64,
+    searches_count: usize,
+    layout: MemoryLayout,
+    rng: &mut StdRng,
+) -> Result<ProgramTrace, Error> {
+    let mut trace = ProgramTrace::new(layout)?;
+    let array = trace.allocate(elements_count);
+    for _ in 0..searches_count {
+        // The array holds 0, 1, 2, ..., so the key is also the index it's found at
+        let key = rng.random_range(0..elements_count.max(1));
+        let (mut low, mut high) = (0, elements_count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            trace.access(array, middle);
+            if middle == key {
+                break;
+            } else if middle < key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+    }
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod programs_tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::paging::least_recently_used;
+
+    // 8 byte elements, 512 of them per page
+    const LAYOUT: MemoryLayout = MemoryLayout {
+        page_size: 4096,
+        element_size: 8,
+    };
+
+    #[test]
+    fn test_allocations_are_page_aligned() {
+        let mut trace = ProgramTrace::new(LAYOUT).unwrap();
+        assert!(trace.allocate(10) == 0);
+        assert!(trace.allocate(513) == 4096);
+        assert!(trace.allocate(1) == 3 * 4096);
+    }
+
+    #[test]
+    fn test_loop_order() {
+        // Rows of 64 elements, 8 rows per page, 8 pages in total
+        let row_major = row_major_traversal(64, 64, LAYOUT)
+            .unwrap()
+            .page_references();
+        let column_major = column_major_traversal(64, 64, LAYOUT)
+            .unwrap()
+            .page_references();
+        assert!(row_major.len() == 4096 && column_major.len() == 4096);
+        assert!(least_recently_used(4, row_major).unwrap().misses == 8);
+        // Every column sweeps over all 8 pages, which don't fit into 4 frames
+        assert!(least_recently_used(4, column_major).unwrap().misses == 8 * 64);
+    }
+
+    #[test]
+    fn test_blocked_matrix_multiply() {
+        // 32 elements per page, so every matrix takes 8 pages
+        let layout = MemoryLayout {
+            page_size: 256,
+            element_size: 8,
+        };
+        let naive = matrix_multiply(16, layout).unwrap();
+        let blocked = blocked_matrix_multiply(16, 8, layout).unwrap();
+        assert!(naive.addresses.len() == 3 * 16 * 16 * 16);
+        assert!(blocked.addresses.len() == naive.addresses.len());
+        let naive_misses = least_recently_used(6, naive.page_references())
+            .unwrap()
+            .misses;
+        let blocked_misses = least_recently_used(6, blocked.page_references())
+            .unwrap()
+            .misses;
+        assert!(blocked_misses * 10 < naive_misses);
+    }
+
+    #[test]
+    fn test_linked_list_walk_and_binary_search() {
+        let walk = linked_list_walk(1024, LAYOUT, &mut StdRng::seed_from_u64(1)).unwrap();
+        let mut visited = walk.addresses.clone();
+        visited.sort();
+        assert!(visited == (0..1024).map(|x| x * 8).collect::<Vec<u64>>());
+        assert!(
+            walk.addresses
+                == linked_list_walk(1024, LAYOUT, &mut StdRng::seed_from_u64(1))
+                    .unwrap()
+                    .addresses
+        );
+
+        let search = binary_search(1024, 1, LAYOUT, &mut StdRng::seed_from_u64(1)).unwrap();
+        // The first probe is always the middle element
+        assert!(search.addresses[0] == 512 * 8);
+        assert!(search.addresses.len() <= 11);
+
+        let layout = MemoryLayout {
+            page_size: 0,
+            element_size: 8,
+        };
+        assert!(binary_search(1024, 1, layout, &mut StdRng::seed_from_u64(1)).is_err());
+    }
+}
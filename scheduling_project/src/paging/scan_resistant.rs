@@ -0,0 +1,279 @@
+use std::collections::VecDeque;
+
+use super::{PagingSimulationResults, PagingSimulator, ReplacementPolicy};
+use crate::error::Error;
+
+/// Queue sizes of the 2Q algorithm, expressed as fractions
+/// of the physical memory size
+pub struct TwoQueueConfig {
+    /// Share of frames the A1in FIFO may hold before
+    /// it starts giving pages away (Kin)
+    pub a1in_ratio: f32,
+    /// Number of remembered A1out page identifiers, relative
+    /// to the physical memory size (Kout)
+    pub a1out_ratio: f32,
+}
+
+impl Default for TwoQueueConfig {
+    /// Values suggested by Johnson and Shasha in the original paper
+    fn default() -> Self {
+        TwoQueueConfig {
+            a1in_ratio: 0.25,
+            a1out_ratio: 0.5,
+        }
+    }
+}
+
+/// Size of the LIRS HIR set, expressed as a fraction
+/// of the physical memory size
+pub struct LirsConfig {
+    pub hir_ratio: f32,
+}
+
+impl Default for LirsConfig {
+    fn default() -> Self {
+        LirsConfig { hir_ratio: 0.01 }
+    }
+}
+
+/// 2Q replacement policy.
+/// Pages seen for the first time land in the A1in FIFO and only get promoted
+/// to the LRU-managed Am queue if they are referenced again shortly after
+/// being evicted (while their identifier is still remembered in A1out).
+/// A one-off sequential scan therefore never pushes the hot pages out of Am.
+pub struct TwoQueue {
+    a1in_size: usize,
+    a1out_size: usize,
+    // Each queue keeps frame indices, the front being the oldest entry
+    a1in: VecDeque<usize>,
+    am: VecDeque<usize>,
+    // A1out only remembers page identifiers, these pages are not resident
+    a1out: VecDeque<u64>,
+}
+
+impl TwoQueue {
+    /// Fails without frames, as A1in needs at least one
+    pub fn new(physical_memory_size: usize, config: TwoQueueConfig) -> Result<TwoQueue, Error> {
+        if physical_memory_size == 0 {
+            return Err(Error::configuration("2Q needs at least one frame"));
+        }
+        Ok(TwoQueue {
+            a1in_size: (physical_memory_size as f32 * config.a1in_ratio)
+                .round()
+                .max(1.0) as usize,
+            a1out_size: (physical_memory_size as f32 * config.a1out_ratio).round() as usize,
+            a1in: VecDeque::new(),
+            am: VecDeque::new(),
+            a1out: VecDeque::new(),
+        })
+    }
+}
+
+impl ReplacementPolicy for TwoQueue {
+    fn on_hit(&mut self, frame: usize, _page: u64) {
+        // Pages in A1in are not touched on a hit, that's what makes 2Q scan-resistant
+        if let Some(position) = self.am.iter().position(|x| *x == frame) {
+            self.am.remove(position);
+            self.am.push_back(frame);
+        }
+    }
+
+    fn on_miss(&mut self, frame: usize, page: u64) {
+        if let Some(position) = self.a1out.iter().position(|x| *x == page) {
+            // Referenced again soon after eviction, the page is considered hot
+            self.a1out.remove(position);
+            self.am.push_back(frame);
+        } else {
+            self.a1in.push_back(frame);
+        }
+    }
+
+    fn choose_victim(&mut self, frames: &[Option<u64>]) -> usize {
+        if self.a1in.len() > self.a1in_size || self.am.is_empty() {
+            let victim = self.a1in.pop_front().unwrap();
+            self.a1out.push_back(frames[victim].unwrap());
+            if self.a1out.len() > self.a1out_size {
+                self.a1out.pop_front();
+            }
+            victim
+        } else {
+            self.am.pop_front().unwrap()
+        }
+    }
+}
+
+/// 2Q algorithm implementation
+pub fn two_queue(
+    physical_memory_size: usize,
+    references: Vec<u64>,
+    config: TwoQueueConfig,
+) -> Result<PagingSimulationResults, Error> {
+    let policy = TwoQueue::new(physical_memory_size, config)?;
+    Ok(PagingSimulator::new(physical_memory_size, policy)?.run(references))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LirsStatus {
+    Lir,
+    ResidentHir,
+    NonResidentHir,
+}
+
+/// LIRS replacement policy.
+/// Pages are split into LIR pages (low inter-reference recency) which are
+/// never evicted and HIR pages which compete for a small share of frames.
+/// A page can only join the LIR set if it is referenced again while its
+/// previous reference is still more recent than the oldest LIR page,
+/// so a sequential scan only ever cycles through the HIR frames.
+pub struct Lirs {
+    lir_size: usize,
+    lir_count: usize,
+    /// Recency stack S, the back being the most recent entry
+    stack: VecDeque<(u64, LirsStatus)>,
+    /// Resident HIR pages, the front being the next victim
+    hir_queue: VecDeque<u64>,
+}
+
+impl Lirs {
+    /// Fails without frames, as at least one is needed for HIR pages
+    pub fn new(physical_memory_size: usize, config: LirsConfig) -> Result<Lirs, Error> {
+        if physical_memory_size == 0 {
+            return Err(Error::configuration("LIRS needs at least one frame"));
+        }
+        let hir_size = (physical_memory_size as f32 * config.hir_ratio)
+            .round()
+            .clamp(1.0, physical_memory_size as f32) as usize;
+        Ok(Lirs {
+            lir_size: physical_memory_size - hir_size,
+            lir_count: 0,
+            stack: VecDeque::new(),
+            hir_queue: VecDeque::new(),
+        })
+    }
+
+    fn access(&mut self, page: u64, resident: bool) {
+        let stack_position = self.stack.iter().position(|(x, _)| *x == page);
+        let status = match stack_position {
+            Some(position) => self.stack[position].1,
+            None if resident => LirsStatus::ResidentHir,
+            None => LirsStatus::NonResidentHir,
+        };
+
+        if status == LirsStatus::Lir {
+            let entry = self.stack.remove(stack_position.unwrap()).unwrap();
+            self.stack.push_back(entry);
+            self.prune_stack();
+        } else if self.lir_count < self.lir_size {
+            // The LIR set is still being filled
+            if let Some(position) = stack_position {
+                self.stack.remove(position);
+            }
+            self.hir_queue.retain(|x| *x != page);
+            self.stack.push_back((page, LirsStatus::Lir));
+            self.lir_count += 1;
+        } else if let Some(position) = stack_position {
+            // The page was referenced recently enough to become a LIR page,
+            // the oldest LIR page gives its place up
+            self.stack.remove(position);
+            self.hir_queue.retain(|x| *x != page);
+            self.stack.push_back((page, LirsStatus::Lir));
+            let (demoted_page, _) = self.stack.pop_front().unwrap();
+            self.hir_queue.push_back(demoted_page);
+            self.prune_stack();
+        } else {
+            self.hir_queue.retain(|x| *x != page);
+            self.hir_queue.push_back(page);
+            self.stack.push_back((page, LirsStatus::ResidentHir));
+        }
+    }
+
+    /// Removes HIR entries from the bottom of the stack,
+    /// so that the oldest entry is always a LIR page
+    fn prune_stack(&mut self) {
+        while let Some((_, status)) = self.stack.front() {
+            if *status == LirsStatus::Lir {
+                break;
+            }
+            self.stack.pop_front();
+        }
+    }
+}
+
+impl ReplacementPolicy for Lirs {
+    fn on_hit(&mut self, _frame: usize, page: u64) {
+        self.access(page, true);
+    }
+
+    fn on_miss(&mut self, _frame: usize, page: u64) {
+        self.access(page, false);
+    }
+
+    fn choose_victim(&mut self, frames: &[Option<u64>]) -> usize {
+        let victim_page = self.hir_queue.pop_front().unwrap();
+        if let Some(entry) = self.stack.iter_mut().find(|(x, _)| *x == victim_page) {
+            entry.1 = LirsStatus::NonResidentHir;
+        }
+        frames.iter().position(|x| *x == Some(victim_page)).unwrap()
+    }
+}
+
+/// LIRS algorithm implementation
+pub fn lirs(
+    physical_memory_size: usize,
+    references: Vec<u64>,
+    config: LirsConfig,
+) -> Result<PagingSimulationResults, Error> {
+    let policy = Lirs::new(physical_memory_size, config)?;
+    Ok(PagingSimulator::new(physical_memory_size, policy)?.run(references))
+}
+
+#[cfg(test)]
+mod scan_resistance_tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::paging::{generate_hot_set_with_scan, least_recently_used};
+
+    #[test]
+    fn test_two_queue_cold_misses() {
+        let references = vec![0, 1, 2, 0, 1, 2];
+        let results = two_queue(3, references, TwoQueueConfig::default()).unwrap();
+        assert!(results.misses == 3);
+        assert!(results.hits == 3);
+        assert!(two_queue(0, vec![0], TwoQueueConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_two_queue_promotes_from_a1out() {
+        // Page 0 is evicted from A1in, then referenced again and lands in Am,
+        // where it survives the next round of one-off references
+        let references = vec![0, 1, 2, 3, 0, 4, 5, 0, 6, 7, 0];
+        let config = TwoQueueConfig {
+            a1in_ratio: 0.25,
+            a1out_ratio: 1.0,
+        };
+        let results = two_queue(4, references, config).unwrap();
+        assert!(results.misses == 9);
+        assert!(results.swap_history.last().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lirs_keeps_lir_pages() {
+        // Pages 0 and 1 become LIR, the rest has to share the single HIR frame
+        let references = vec![0, 1, 2, 3, 4, 5, 0, 1];
+        let results = lirs(3, references, LirsConfig::default()).unwrap();
+        assert!(results.misses == 6);
+        assert!(results.hits == 2);
+        assert!(lirs(0, vec![0], LirsConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_scan_resistance() {
+        let references = generate_hot_set_with_scan(8, 200, 100, &mut StdRng::seed_from_u64(1));
+        let lru = least_recently_used(10, references.clone()).unwrap();
+        let two_queue = two_queue(10, references.clone(), TwoQueueConfig::default()).unwrap();
+        let lirs = lirs(10, references, LirsConfig { hir_ratio: 0.2 }).unwrap();
+        assert!(two_queue.misses < lru.misses);
+        assert!(lirs.misses < lru.misses);
+    }
+}
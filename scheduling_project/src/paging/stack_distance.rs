@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+/// Results of a single-pass LRU stack distance analysis
+pub struct StackDistanceResults {
+    pub references_count: usize,
+    /// Number of distinct pages referenced
+    pub distinct_pages_count: usize,
+    /// First references to a page, these miss regardless of memory size
+    pub cold_misses: usize,
+    /// `reuse_distance_histogram[d]` is the number of references that had
+    /// exactly `d` distinct other pages referenced since the previous
+    /// reference to the same page (LRU stack distance minus one)
+    pub reuse_distance_histogram: Vec<usize>,
+    /// `misses[size]` is the number of misses least recently used
+    /// would have with `size` frames of physical memory
+    pub misses: Vec<usize>,
+    /// `miss_ratio_curve[size]` is `misses[size]` divided by the references count
+    pub miss_ratio_curve: Vec<f32>,
+}
+
+/// Mattson's stack distance analysis.
+/// Computes least recently used misses for every physical memory size
+/// in a single pass over the reference string.
+/// Pages are tracked by the position of their most recent reference in
+/// a Fenwick tree, so counting distinct pages referenced since the previous
+/// reference to the same page takes O(log n) instead of walking the LRU stack.
+pub fn stack_distance_analysis(references: &[u64]) -> StackDistanceResults {
+    let mut last_position: HashMap<u64, usize> = HashMap::new();
+    let mut most_recent_references = FenwickTree::new(references.len());
+    let mut reuse_distance_histogram: Vec<usize> = vec![];
+    let mut cold_misses = 0;
+
+    for (position, reference) in references.iter().enumerate() {
+        if let Some(previous_position) = last_position.insert(*reference, position) {
+            let reuse_distance = most_recent_references.sum(position)
+                - most_recent_references.sum(previous_position + 1);
+            if reuse_distance_histogram.len() <= reuse_distance {
+                reuse_distance_histogram.resize(reuse_distance + 1, 0);
+            }
+            reuse_distance_histogram[reuse_distance] += 1;
+            most_recent_references.add(previous_position, -1);
+        } else {
+            cold_misses += 1;
+        }
+        most_recent_references.add(position, 1);
+    }
+
+    let distinct_pages_count = last_position.len();
+    // A reference hits if there are more frames than its reuse distance
+    let mut misses: Vec<usize> = vec![references.len()];
+    for size in 1..=distinct_pages_count {
+        let hits_gained = reuse_distance_histogram.get(size - 1).copied().unwrap_or(0);
+        misses.push(misses[size - 1] - hits_gained);
+    }
+    let miss_ratio_curve = misses
+        .iter()
+        .map(|x| *x as f32 / references.len().max(1) as f32)
+        .collect();
+    StackDistanceResults {
+        references_count: references.len(),
+        distinct_pages_count,
+        cold_misses,
+        reuse_distance_histogram,
+        misses,
+        miss_ratio_curve,
+    }
+}
+
+impl StackDistanceResults {
+    /// Number of least recently used misses with a given physical memory size
+    pub fn misses_for(&self, physical_memory_size: usize) -> usize {
+        let size = physical_memory_size.min(self.distinct_pages_count);
+        self.misses[size]
+    }
+}
+
+/// Binary indexed tree keeping prefix sums of a fixed size array
+struct FenwickTree {
+    tree: Vec<i64>,
+}
+
+impl FenwickTree {
+    fn new(size: usize) -> FenwickTree {
+        FenwickTree {
+            tree: vec![0; size + 1],
+        }
+    }
+
+    fn add(&mut self, index: usize, value: i64) {
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += value;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sum of the first `count` elements
+    fn sum(&self, count: usize) -> usize {
+        let mut i = count;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum as usize
+    }
+}
+
+#[cfg(test)]
+mod stack_distance_tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::paging::{generate_normal_distribution_of_references, least_recently_used};
+
+    #[test]
+    fn test_reuse_distances() {
+        let references = vec![0, 1, 2, 0, 0, 1];
+        let results = stack_distance_analysis(&references);
+        assert!(results.cold_misses == 3);
+        assert!(results.reuse_distance_histogram == vec![1, 0, 2]);
+        assert!(results.misses == vec![6, 5, 5, 3]);
+    }
+
+    #[test]
+    fn test_matches_least_recently_used() {
+        let references =
+            generate_normal_distribution_of_references(32, 8.0, 4.0, &mut StdRng::seed_from_u64(1))
+                .unwrap();
+        let results = stack_distance_analysis(&references);
+        for size in 1..=40 {
+            let least_recently_used = least_recently_used(size, references.clone()).unwrap();
+            assert!(results.misses_for(size) == least_recently_used.misses);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use super::PagingSimulationResults;
+
+/// A single step of a paging simulation
+pub struct PagingStep {
+    pub reference: u64,
+    pub hit: bool,
+    /// Frame the page was loaded into, None on a hit
+    pub victim_frame: Option<usize>,
+    /// Page that was replaced, None on a hit or if the frame was free
+    pub evicted_page: Option<u64>,
+    /// Contents of every frame right after the reference
+    pub frames: Vec<Option<u64>>,
+}
+
+/// Textbook-style paging diagram: references across the top,
+/// frames down the side and faults marked at the bottom
+struct TraceGrid {
+    references: Vec<String>,
+    /// Frame contents, one row per frame. True marks a page loaded in that step
+    frames: Vec<Vec<(String, bool)>>,
+    faults: Vec<String>,
+}
+
+/// Utility functions rendering the per-reference trace,
+/// so that simulations can be put straight into lecture slides
+impl PagingSimulationResults {
+    pub fn trace_to_ascii(&self) -> String {
+        let grid = self.trace_grid();
+        let label_width = grid.labels().map(|label| label.len()).max().unwrap_or(0);
+        let cell_width = grid
+            .references
+            .iter()
+            .chain(grid.frames.iter().flatten().map(|(cell, _)| cell))
+            .map(|cell| cell.len())
+            .max()
+            .unwrap_or(0);
+        let row = |label: &str, cells: Vec<&str>| {
+            let mut row = format!("{:<label_width$} |", label);
+            for cell in cells {
+                row += &format!(" {:>cell_width$} |", cell);
+            }
+            row + "\n"
+        };
+        let separator = format!(
+            "{}-+{}\n",
+            "-".repeat(label_width),
+            format!("{}+", "-".repeat(cell_width + 2)).repeat(grid.references.len())
+        );
+
+        let mut output = row(
+            "Reference",
+            grid.references.iter().map(|x| x.as_str()).collect(),
+        );
+        output += &separator;
+        for (i, frame) in grid.frames.iter().enumerate() {
+            let cells = frame.iter().map(|(cell, _)| cell.as_str()).collect();
+            output += &row(&format!("Frame {}", i), cells);
+        }
+        output += &separator;
+        output += &row("Fault", grid.faults.iter().map(|x| x.as_str()).collect());
+        output
+    }
+
+    /// Pages loaded in a given step are in bold
+    pub fn trace_to_markdown(&self) -> String {
+        let grid = self.trace_grid();
+        let row =
+            |label: &str, cells: Vec<String>| format!("| {} | {} |\n", label, cells.join(" | "));
+
+        let mut output = row("Reference", grid.references.clone());
+        output += &format!("|---|{}\n", "---|".repeat(grid.references.len()));
+        for (i, frame) in grid.frames.iter().enumerate() {
+            let cells = frame
+                .iter()
+                .map(|(cell, loaded)| match loaded {
+                    true => format!("**{}**", cell),
+                    false => cell.clone(),
+                })
+                .collect();
+            output += &row(&format!("Frame {}", i), cells);
+        }
+        output += &row("Fault", grid.faults.clone());
+        output
+    }
+
+    /// Pages loaded in a given step are in bold
+    pub fn trace_to_latex(&self) -> String {
+        let grid = self.trace_grid();
+        let row = |label: &str, cells: Vec<String>| {
+            let mut row = label.to_owned();
+            for cell in cells {
+                row += &format!(" & {}", cell);
+            }
+            row + " \\\\\n"
+        };
+
+        let mut output = format!(
+            "\\begin{{tabular}}{{|l|{}}}\n\\hline\n",
+            "c|".repeat(grid.references.len())
+        );
+        output += &row("Reference", grid.references.clone());
+        output += "\\hline\n";
+        for (i, frame) in grid.frames.iter().enumerate() {
+            let cells = frame
+                .iter()
+                .map(|(cell, loaded)| match loaded {
+                    true => format!("\\textbf{{{}}}", cell),
+                    false => cell.clone(),
+                })
+                .collect();
+            output += &row(&format!("Frame {}", i), cells);
+        }
+        output += "\\hline\n";
+        output += &row("Fault", grid.faults.clone());
+        output += "\\hline\n\\end{tabular}\n";
+        output
+    }
+
+    fn trace_grid(&self) -> TraceGrid {
+        let frames_count = self
+            .trace
+            .iter()
+            .map(|step| step.frames.len())
+            .max()
+            .unwrap_or(0);
+        let frames = (0..frames_count)
+            .map(|frame| {
+                self.trace
+                    .iter()
+                    .map(|step| {
+                        let cell = match step.frames.get(frame) {
+                            Some(Some(page)) => page.to_string(),
+                            _ => "".to_owned(),
+                        };
+                        (cell, step.victim_frame == Some(frame))
+                    })
+                    .collect()
+            })
+            .collect();
+        TraceGrid {
+            references: self.trace.iter().map(|x| x.reference.to_string()).collect(),
+            frames,
+            faults: self
+                .trace
+                .iter()
+                .map(|step| match step.hit {
+                    true => "".to_owned(),
+                    false => "F".to_owned(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TraceGrid {
+    fn labels(&self) -> impl Iterator<Item = String> {
+        let frame_labels = (0..self.frames.len()).map(|i| format!("Frame {}", i));
+        ["Reference".to_owned(), "Fault".to_owned()]
+            .into_iter()
+            .chain(frame_labels)
+    }
+}
+
+#[cfg(test)]
+mod trace_tests {
+    use crate::paging::{Fifo, PagingSimulator};
+
+    #[test]
+    fn test_trace_steps() {
+        let references = vec![0, 1, 2, 0, 3];
+        let results = PagingSimulator::new(3, Fifo::default())
+            .unwrap()
+            .with_trace()
+            .run(references);
+        assert!(results.trace.len() == 5);
+        // Evictions into free frames are logged as well
+        assert!(results.trace[1].victim_frame == Some(1));
+        assert!(results.trace[1].evicted_page.is_none());
+        assert!(results.trace[3].hit);
+        assert!(results.trace[4].evicted_page == Some(0));
+        assert!(results.trace[4].frames == vec![Some(3), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_ascii_trace() {
+        let results = PagingSimulator::new(2, Fifo::default())
+            .unwrap()
+            .with_trace()
+            .run(vec![7, 0, 7, 1]);
+        let expected = "\
+Reference | 7 | 0 | 7 | 1 |
+----------+---+---+---+---+
+Frame 0   | 7 | 7 | 7 | 1 |
+Frame 1   |   | 0 | 0 | 0 |
+----------+---+---+---+---+
+Fault     | F | F |   | F |
+";
+        assert!(results.trace_to_ascii() == expected);
+    }
+
+    #[test]
+    fn test_markdown_and_latex_trace() {
+        let results = PagingSimulator::new(1, Fifo::default())
+            .unwrap()
+            .with_trace()
+            .run(vec![4, 4]);
+        assert!(
+            results.trace_to_markdown()
+                == "| Reference | 4 | 4 |\n|---|---|---|\n| Frame 0 | **4** | 4 |\n| Fault | F |  |\n"
+        );
+        assert!(results
+            .trace_to_latex()
+            .contains("Frame 0 & \\textbf{4} & 4 \\\\\n"));
+    }
+}
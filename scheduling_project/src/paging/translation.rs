@@ -0,0 +1,288 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{PagingSimulationResults, PagingSimulator, ReplacementPolicy};
+use crate::error::Error;
+
+#[derive(Clone, Copy)]
+/// Replacement algorithm used within a single TLB set
+pub enum TlbReplacement {
+    LeastRecentlyUsed,
+    Fifo,
+    /// Victims picked with a generator seeded with `seed`
+    Random {
+        seed: u64,
+    },
+}
+
+/// TLB geometry. A fully associative TLB has `associativity` equal to `entries`
+pub struct TlbConfig {
+    pub entries: usize,
+    pub associativity: usize,
+    pub replacement: TlbReplacement,
+}
+
+/// Access latencies used to compute the effective access time,
+/// all expressed in the same unit (e.g. nanoseconds)
+pub struct AccessLatencies {
+    pub tlb: f32,
+    pub memory: f32,
+    /// Time needed to service a page fault from disk
+    pub page_fault: f32,
+}
+
+/// Parameters of a virtual address translation simulation
+pub struct AddressTranslationConfig {
+    /// Page size in bytes, has to be a power of two
+    pub page_size: u64,
+    /// Number of page number bits translated by every page table level,
+    /// starting from the outermost one. A single element means a single-level table.
+    pub page_table_levels: Vec<u32>,
+    pub tlb: TlbConfig,
+    pub latencies: AccessLatencies,
+}
+
+impl AddressTranslationConfig {
+    /// Checks that the page size is a power of two and that the
+    /// page table levels and the offset fit in a 64-bit address
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.page_size.is_power_of_two() {
+            return Err(Error::configuration(format!(
+                "page size {} is not a power of two",
+                self.page_size
+            )));
+        }
+        let offset_bits = self.page_size.trailing_zeros();
+        let level_bits: u64 = self.page_table_levels.iter().map(|x| *x as u64).sum();
+        if level_bits >= 64 || level_bits + offset_bits as u64 > 64 {
+            return Err(Error::configuration(format!(
+                "{} page table bits and {} offset bits don't fit in a 64-bit address",
+                level_bits, offset_bits
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Results of a virtual address translation simulation
+pub struct AddressTranslationResults {
+    /// Replacement policy results for the page numbers that were referenced
+    pub paging: PagingSimulationResults,
+    pub accesses: usize,
+    pub tlb_hits: usize,
+    pub tlb_misses: usize,
+    pub tlb_hit_rate: f32,
+    pub page_table_walks: usize,
+    /// Number of page tables allocated across all levels
+    pub page_table_count: usize,
+    pub total_access_time: f32,
+    pub effective_access_time: f32,
+}
+
+/// Splits a virtual address into a page number and an offset within the page
+pub fn split_virtual_address(address: u64, page_size: u64) -> (u64, u64) {
+    (address / page_size, address % page_size)
+}
+
+/// Simulates translating byte-level virtual addresses.
+/// Every access looks the page up in the TLB first. On a TLB miss the page
+/// table is walked (one memory access per level) and if the page isn't
+/// resident, a page fault is serviced by the given replacement policy.
+/// TLB entries of evicted pages are invalidated.
+pub fn translate_addresses<P: ReplacementPolicy>(
+    addresses: Vec<u64>,
+    physical_memory_size: usize,
+    policy: P,
+    config: AddressTranslationConfig,
+) -> Result<AddressTranslationResults, Error> {
+    config.validate()?;
+    let mut simulator = PagingSimulator::new(physical_memory_size, policy)?;
+    let mut tlb = Tlb::new(&config.tlb);
+    let mut allocated_page_tables: HashSet<(usize, u64)> = HashSet::new();
+    let latencies = &config.latencies;
+    let levels_count = config.page_table_levels.len();
+    let mut tlb_hits = 0;
+    let mut page_table_walks = 0;
+    let mut total_access_time: f32 = 0.0;
+
+    for address in &addresses {
+        let (page_number, _) = split_virtual_address(*address, config.page_size);
+        total_access_time += latencies.tlb + latencies.memory;
+        if tlb.lookup(page_number) {
+            tlb_hits += 1;
+            simulator.reference(page_number);
+            continue;
+        }
+
+        page_table_walks += 1;
+        total_access_time += latencies.memory * levels_count as f32;
+        // Every level is indexed by the page number bits left of the next levels
+        let mut remaining_bits: u32 = config.page_table_levels.iter().sum();
+        for (level, bits) in config.page_table_levels.iter().enumerate() {
+            remaining_bits -= bits;
+            allocated_page_tables.insert((level, page_number >> (remaining_bits + bits)));
+        }
+
+        if !simulator.reference(page_number) {
+            total_access_time += latencies.page_fault;
+            tlb.retain(|cached_page| simulator.contains(cached_page));
+        }
+        tlb.insert(page_number);
+    }
+
+    let accesses = addresses.len();
+    Ok(AddressTranslationResults {
+        paging: simulator.results(),
+        accesses,
+        tlb_hits,
+        tlb_misses: accesses - tlb_hits,
+        tlb_hit_rate: tlb_hits as f32 / accesses.max(1) as f32,
+        page_table_walks,
+        page_table_count: allocated_page_tables.len(),
+        total_access_time,
+        effective_access_time: total_access_time / accesses.max(1) as f32,
+    })
+}
+
+/// Set-associative translation lookaside buffer
+struct Tlb {
+    /// Page numbers cached in every set, the front being the next victim
+    /// for FIFO and least recently used replacement
+    sets: Vec<VecDeque<u64>>,
+    associativity: usize,
+    replacement: TlbReplacement,
+    rng: StdRng,
+}
+
+impl Tlb {
+    fn new(config: &TlbConfig) -> Tlb {
+        let associativity = config.associativity.clamp(1, config.entries.max(1));
+        Tlb {
+            sets: vec![VecDeque::new(); (config.entries / associativity).max(1)],
+            associativity,
+            replacement: config.replacement,
+            rng: match config.replacement {
+                TlbReplacement::Random { seed } => StdRng::seed_from_u64(seed),
+                _ => StdRng::seed_from_u64(0),
+            },
+        }
+    }
+
+    fn lookup(&mut self, page_number: u64) -> bool {
+        let index = (page_number % self.sets.len() as u64) as usize;
+        let set = &mut self.sets[index];
+        let Some(position) = set.iter().position(|x| *x == page_number) else {
+            return false;
+        };
+        if let TlbReplacement::LeastRecentlyUsed = self.replacement {
+            set.remove(position);
+            set.push_back(page_number);
+        }
+        true
+    }
+
+    fn insert(&mut self, page_number: u64) {
+        let index = (page_number % self.sets.len() as u64) as usize;
+        let set = &mut self.sets[index];
+        if set.len() >= self.associativity {
+            match self.replacement {
+                TlbReplacement::LeastRecentlyUsed | TlbReplacement::Fifo => {
+                    set.pop_front();
+                }
+                TlbReplacement::Random { .. } => {
+                    let victim = self.rng.random_range(0..set.len());
+                    set.remove(victim);
+                }
+            }
+        }
+        set.push_back(page_number);
+    }
+
+    fn retain<F: Fn(u64) -> bool>(&mut self, predicate: F) {
+        for set in self.sets.iter_mut() {
+            set.retain(|page_number| predicate(*page_number));
+        }
+    }
+}
+
+#[cfg(test)]
+mod translation_tests {
+    use super::*;
+    use crate::paging::LeastRecentlyUsed;
+
+    fn get_config(entries: usize, page_table_levels: Vec<u32>) -> AddressTranslationConfig {
+        AddressTranslationConfig {
+            page_size: 4096,
+            page_table_levels,
+            tlb: TlbConfig {
+                entries,
+                associativity: entries,
+                replacement: TlbReplacement::LeastRecentlyUsed,
+            },
+            latencies: AccessLatencies {
+                tlb: 1.0,
+                memory: 100.0,
+                page_fault: 10_000.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_split_virtual_address() {
+        assert!(split_virtual_address(0x1234, 4096) == (1, 0x234));
+        assert!(split_virtual_address(4095, 4096) == (0, 4095));
+    }
+
+    #[test]
+    fn test_tlb_and_page_table_walks() {
+        // Four accesses within the first page, then one to the second page
+        let addresses = vec![0, 8, 16, 24, 4096];
+        let results = translate_addresses(
+            addresses,
+            4,
+            LeastRecentlyUsed::default(),
+            get_config(2, vec![10, 10]),
+        )
+        .unwrap();
+        assert!(results.tlb_hits == 3);
+        assert!(results.page_table_walks == 2);
+        assert!(results.paging.misses == 2);
+        // Both pages share the outer table and the first inner table
+        assert!(results.page_table_count == 2);
+        // 5 TLB and memory accesses, 2 two-level walks and 2 page faults
+        assert!(results.total_access_time == 5.0 * 101.0 + 2.0 * 200.0 + 2.0 * 10_000.0);
+    }
+
+    #[test]
+    fn test_tlb_invalidated_on_eviction() {
+        // With a single frame, page 0 is evicted when page 1 is loaded
+        let addresses = vec![0, 4096, 0];
+        let results = translate_addresses(
+            addresses,
+            1,
+            LeastRecentlyUsed::default(),
+            get_config(4, vec![20]),
+        )
+        .unwrap();
+        assert!(results.tlb_hits == 0);
+        assert!(results.paging.misses == 3);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut config = get_config(2, vec![10, 10]);
+        config.page_size = 0;
+        assert!(config.validate().is_err());
+        config.page_size = 3000;
+        assert!(config.validate().is_err());
+        config.page_size = 4096;
+        config.page_table_levels = vec![26, 26];
+        assert!(config.validate().is_ok());
+        config.page_table_levels = vec![26, 27];
+        assert!(config.validate().is_err());
+        config.page_size = 1;
+        config.page_table_levels = vec![32, 32];
+        assert!(config.validate().is_err());
+    }
+}
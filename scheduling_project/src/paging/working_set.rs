@@ -0,0 +1,254 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{PagingSimulationResults, PagingSimulator, ReplacementPolicy};
+use crate::error::Error;
+
+#[derive(Clone, Copy)]
+/// Size of the working set window Δ
+pub enum WorkingSetWindow {
+    /// Δ most recent references
+    References(usize),
+    /// References made within the last Δ units of virtual time
+    VirtualTime(f32),
+}
+
+/// Results of a working set model simulation
+pub struct WorkingSetResults {
+    pub references: Vec<u64>,
+    /// Working set size W(t, Δ) right after each reference
+    pub working_set_sizes: Vec<usize>,
+    pub average_working_set_size: f32,
+    pub peak_working_set_size: usize,
+    /// Page faults that would occur if memory held exactly the working set
+    pub misses: usize,
+}
+
+/// Working set model simulation.
+/// `reference_times` holds the virtual time of every reference and is only
+/// used with `WorkingSetWindow::VirtualTime`. When it's omitted,
+/// each reference takes one unit of virtual time.
+/// Fails if it doesn't hold a time for every reference.
+pub fn working_set(
+    references: Vec<u64>,
+    reference_times: Option<Vec<f32>>,
+    window: WorkingSetWindow,
+) -> Result<WorkingSetResults, Error> {
+    let (times, delta) = get_window_clock(references.len(), reference_times, window)?;
+    let mut working_set_sizes: Vec<usize> = vec![];
+    let mut misses = 0;
+    let mut last_use: HashMap<u64, f32> = HashMap::new();
+    // References within the window, the front being the oldest one
+    let mut window_references: VecDeque<(f32, u64)> = VecDeque::new();
+    let mut window_counts: HashMap<u64, usize> = HashMap::new();
+
+    for (i, reference) in references.iter().enumerate() {
+        let time = times[i];
+        // Memory holds W(t - 1, Δ) when the reference is made
+        let resident = match last_use.get(reference) {
+            Some(used_at) => time - *used_at <= delta,
+            None => false,
+        };
+        if !resident {
+            misses += 1;
+        }
+        last_use.insert(*reference, time);
+
+        window_references.push_back((time, *reference));
+        *window_counts.entry(*reference).or_insert(0) += 1;
+        while let Some((used_at, page)) = window_references.front().copied() {
+            if used_at > time - delta {
+                break;
+            }
+            window_references.pop_front();
+            let count = window_counts.get_mut(&page).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                window_counts.remove(&page);
+            }
+        }
+        working_set_sizes.push(window_counts.len());
+    }
+
+    let average_working_set_size = working_set_sizes.iter().map(|x| *x as f32).sum::<f32>()
+        / working_set_sizes.len().max(1) as f32;
+    let peak_working_set_size = working_set_sizes.iter().copied().max().unwrap_or(0);
+    Ok(WorkingSetResults {
+        references,
+        working_set_sizes,
+        average_working_set_size,
+        peak_working_set_size,
+        misses,
+    })
+}
+
+struct ClockFrame {
+    referenced: bool,
+    last_use: f32,
+}
+
+/// WSClock replacement policy.
+/// The clock hand skips pages referenced since its last visit and evicts
+/// the first page that fell out of the working set (older than Δ).
+/// If every page is still in the working set, the oldest one is evicted.
+pub struct WsClock {
+    times: Vec<f32>,
+    delta: f32,
+    /// Index of the reference being simulated
+    current_reference: usize,
+    frames: Vec<Option<ClockFrame>>,
+    hand: usize,
+}
+
+impl WsClock {
+    /// See `working_set` for the meaning of `reference_times`
+    pub fn new(
+        references_count: usize,
+        reference_times: Option<Vec<f32>>,
+        window: WorkingSetWindow,
+    ) -> Result<WsClock, Error> {
+        let (times, delta) = get_window_clock(references_count, reference_times, window)?;
+        Ok(WsClock {
+            times,
+            delta,
+            current_reference: 0,
+            frames: vec![],
+            hand: 0,
+        })
+    }
+}
+
+impl ReplacementPolicy for WsClock {
+    fn on_hit(&mut self, frame: usize, _page: u64) {
+        self.frames[frame].as_mut().unwrap().referenced = true;
+        self.current_reference += 1;
+    }
+
+    fn on_miss(&mut self, frame: usize, _page: u64) {
+        if self.frames.len() <= frame {
+            self.frames.resize_with(frame + 1, || None);
+        }
+        self.frames[frame] = Some(ClockFrame {
+            referenced: false,
+            last_use: self.times[self.current_reference],
+        });
+        self.current_reference += 1;
+    }
+
+    /// Moves the clock hand until it finds a page outside the working set.
+    /// Gives up after a full circle and falls back to the oldest page.
+    fn choose_victim(&mut self, _frames: &[Option<u64>]) -> usize {
+        let time = self.times[self.current_reference];
+        for _ in 0..self.frames.len() {
+            let index = self.hand;
+            self.hand = (self.hand + 1) % self.frames.len();
+            let Some(frame) = self.frames[index].as_mut() else {
+                continue;
+            };
+            if frame.referenced {
+                frame.referenced = false;
+                frame.last_use = time;
+            } else if time - frame.last_use > self.delta {
+                self.frames[index] = None;
+                return index;
+            }
+        }
+        let oldest = self
+            .frames
+            .iter()
+            .enumerate()
+            .filter_map(|(index, frame)| frame.as_ref().map(|frame| (index, frame.last_use)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+            .unwrap();
+        self.frames[oldest] = None;
+        self.hand = (oldest + 1) % self.frames.len();
+        oldest
+    }
+}
+
+/// WSClock algorithm implementation
+pub fn ws_clock(
+    physical_memory_size: usize,
+    references: Vec<u64>,
+    reference_times: Option<Vec<f32>>,
+    window: WorkingSetWindow,
+) -> Result<PagingSimulationResults, Error> {
+    if physical_memory_size == 0 {
+        return Err(Error::configuration("WSClock needs at least one frame"));
+    }
+    let policy = WsClock::new(references.len(), reference_times, window)?;
+    Ok(PagingSimulator::new(physical_memory_size, policy)?.run(references))
+}
+
+/// Returns virtual time of every reference and the window length
+/// expressed in the same units
+fn get_window_clock(
+    references_count: usize,
+    reference_times: Option<Vec<f32>>,
+    window: WorkingSetWindow,
+) -> Result<(Vec<f32>, f32), Error> {
+    if let Some(times) = &reference_times {
+        if times.len() != references_count {
+            return Err(Error::configuration(format!(
+                "{} reference times given for {} references",
+                times.len(),
+                references_count
+            )));
+        }
+    }
+    let reference_indices = || (0..references_count).map(|i| i as f32).collect();
+    Ok(match window {
+        WorkingSetWindow::References(delta) => (reference_indices(), delta as f32),
+        WorkingSetWindow::VirtualTime(delta) => {
+            (reference_times.unwrap_or_else(reference_indices), delta)
+        }
+    })
+}
+
+#[cfg(test)]
+mod working_set_tests {
+    use super::*;
+
+    #[test]
+    fn test_working_set_sizes() {
+        let references = vec![0, 1, 0, 2, 3, 3, 3, 3];
+        let results = working_set(references, None, WorkingSetWindow::References(3)).unwrap();
+        assert!(results.working_set_sizes == vec![1, 2, 2, 3, 3, 2, 1, 1]);
+        assert!(results.peak_working_set_size == 3);
+        assert!(results.misses == 4);
+    }
+
+    #[test]
+    fn test_working_set_virtual_time() {
+        // Page 0 is referenced again after a long pause and has to be loaded again
+        let references = vec![0, 1, 0];
+        let times = vec![0.0, 1.0, 10.0];
+        let results = working_set(
+            references.clone(),
+            Some(times),
+            WorkingSetWindow::VirtualTime(5.0),
+        )
+        .unwrap();
+        assert!(results.working_set_sizes == vec![1, 2, 1]);
+        assert!(results.misses == 3);
+
+        let short_times = Some(vec![0.0, 1.0]);
+        let window = WorkingSetWindow::VirtualTime(5.0);
+        let error = working_set(references.clone(), short_times.clone(), window)
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+        assert!(ws_clock(2, references.clone(), short_times, window).is_err());
+        assert!(ws_clock(0, references, None, window).is_err());
+    }
+
+    #[test]
+    fn test_ws_clock() {
+        let references = vec![0, 1, 2, 0, 3, 0, 4];
+        let results = ws_clock(3, references, None, WorkingSetWindow::References(2)).unwrap();
+        assert!(results.hits == 2);
+        assert!(results.misses == 5);
+        // Page 1 is the first one outside the working set, page 0 is kept
+        assert!(results.swap_history == vec![None, Some(1), None, Some(2)]);
+    }
+}
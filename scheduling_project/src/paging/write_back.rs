@@ -0,0 +1,143 @@
+use rand::{
+    distr::{Bernoulli, Distribution},
+    rngs::StdRng,
+};
+
+use super::{PagingSimulationResults, PagingSimulator, ReplacementPolicy};
+use crate::error::Error;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// A page reference tagged as a read or a write
+pub struct PageAccess {
+    pub page: u64,
+    pub write: bool,
+}
+
+/// Costs used to compute I/O time and the effective access time,
+/// all expressed in the same unit (e.g. microseconds)
+pub struct IoCostModel {
+    pub memory_access: f32,
+    /// Time needed to read a missing page in
+    pub fault_service: f32,
+    /// Time needed to write a dirty page out before its frame can be reused
+    pub write_back: f32,
+}
+
+/// Results of a paging simulation with dirty page write-back
+pub struct WriteBackResults {
+    pub paging: PagingSimulationResults,
+    pub reads: usize,
+    pub writes: usize,
+    pub clean_evictions: usize,
+    pub dirty_evictions: usize,
+    /// Time spent servicing faults and writing dirty pages back
+    pub total_io_time: f32,
+    pub effective_access_time: f32,
+}
+
+/// Simulates reads and writes, charging a write-back for every
+/// dirty page that gets evicted on top of the fault service time
+pub fn simulate_write_back<P: ReplacementPolicy>(
+    accesses: Vec<PageAccess>,
+    physical_memory_size: usize,
+    policy: P,
+    costs: IoCostModel,
+) -> Result<WriteBackResults, Error> {
+    let mut simulator = PagingSimulator::new(physical_memory_size, policy)?;
+    let writes = accesses.iter().filter(|x| x.write).count();
+    for access in &accesses {
+        simulator.access(access.page, access.write);
+    }
+    let paging = simulator.results();
+
+    let total_io_time =
+        paging.misses as f32 * costs.fault_service + paging.write_backs as f32 * costs.write_back;
+    let total_access_time = accesses.len() as f32 * costs.memory_access + total_io_time;
+    Ok(WriteBackResults {
+        reads: accesses.len() - writes,
+        writes,
+        clean_evictions: paging.evictions - paging.write_backs,
+        dirty_evictions: paging.write_backs,
+        total_io_time,
+        effective_access_time: total_access_time / accesses.len().max(1) as f32,
+        paging,
+    })
+}
+
+/// Marks every reference as a write with the given probability
+pub fn tag_writes(
+    references: Vec<u64>,
+    write_probability: f64,
+    rng: &mut StdRng,
+) -> Result<Vec<PageAccess>, Error> {
+    let writes = Bernoulli::new(write_probability).map_err(|e| {
+        Error::configuration(format!("write probability {}: {}", write_probability, e))
+    })?;
+    Ok(references
+        .into_iter()
+        .map(|page| PageAccess {
+            page,
+            write: writes.sample(rng),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod write_back_tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::paging::{Fifo, LeastRecentlyUsed};
+
+    fn get_costs() -> IoCostModel {
+        IoCostModel {
+            memory_access: 1.0,
+            fault_service: 100.0,
+            write_back: 50.0,
+        }
+    }
+
+    fn read(page: u64) -> PageAccess {
+        PageAccess { page, write: false }
+    }
+
+    fn write(page: u64) -> PageAccess {
+        PageAccess { page, write: true }
+    }
+
+    #[test]
+    fn test_dirty_pages_are_written_back() {
+        // Page 0 is dirtied on a hit, page 1 is loaded by a write
+        let accesses = vec![read(0), write(0), write(1), read(2), read(3), read(0)];
+        let results = simulate_write_back(accesses, 2, Fifo::default(), get_costs()).unwrap();
+        assert!((results.reads, results.writes) == (4, 2));
+        assert!(results.paging.misses == 5);
+        // 2 evicts 0 (dirty), 3 evicts 1 (dirty), 0 evicts 2 (clean)
+        assert!(results.dirty_evictions == 2);
+        assert!(results.clean_evictions == 1);
+        assert!(results.total_io_time == 5.0 * 100.0 + 2.0 * 50.0);
+        assert!(results.effective_access_time == (6.0 + 600.0) / 6.0);
+    }
+
+    #[test]
+    fn test_written_back_page_is_clean_after_reload() {
+        let accesses = vec![write(0), read(1), read(0), read(1)];
+        let results =
+            simulate_write_back(accesses, 1, LeastRecentlyUsed::default(), get_costs()).unwrap();
+        assert!(results.paging.evictions == 3);
+        assert!(results.dirty_evictions == 1);
+    }
+
+    #[test]
+    fn test_tag_writes() {
+        let references: Vec<u64> = (0..1000).collect();
+        let accesses = tag_writes(references.clone(), 0.25, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert!(
+            accesses
+                == tag_writes(references.clone(), 0.25, &mut StdRng::seed_from_u64(7)).unwrap()
+        );
+        assert!(tag_writes(references, 1.5, &mut StdRng::seed_from_u64(7)).is_err());
+        let writes = accesses.iter().filter(|x| x.write).count();
+        assert!((150..350).contains(&writes));
+    }
+}
@@ -0,0 +1,324 @@
+use rand::{rngs::StdRng, Rng};
+use rand_distr::{Distribution, Exp};
+
+use super::read_json_from;
+use crate::error::Error;
+
+/// Source of process arrival times. Every call returns the arrival time
+/// of the next process, None once there are no more arrivals.
+pub trait ArrivalProcess {
+    fn next_arrival(&mut self, rng: &mut StdRng) -> Option<f32>;
+}
+
+/// Original `generate_queue` arrivals: with probability `step_probability`
+/// the next process arrives uniform(0, 1) after the previous one,
+/// otherwise at the same time
+pub struct UniformSteps {
+    step_probability: f64,
+    time: f32,
+}
+
+impl UniformSteps {
+    pub fn new(step_probability: f64) -> Result<UniformSteps, Error> {
+        if !(0.0..=1.0).contains(&step_probability) {
+            return Err(Error::configuration(format!(
+                "step probability {} is not within [0, 1]",
+                step_probability
+            )));
+        }
+        Ok(UniformSteps {
+            step_probability,
+            time: 0.0,
+        })
+    }
+
+    pub fn step_probability(&self) -> f64 {
+        self.step_probability
+    }
+}
+
+impl Default for UniformSteps {
+    fn default() -> Self {
+        UniformSteps {
+            step_probability: 0.9,
+            time: 0.0,
+        }
+    }
+}
+
+impl ArrivalProcess for UniformSteps {
+    fn next_arrival(&mut self, rng: &mut StdRng) -> Option<f32> {
+        if rng.random_bool(self.step_probability) {
+            let step: f32 = rng.random();
+            self.time += step;
+        }
+        Some(self.time)
+    }
+}
+
+/// Arrivals with exponentially distributed gaps, `rate` arrivals per time unit on average
+pub struct Poisson {
+    gaps: Exp<f32>,
+    time: f32,
+}
+
+impl Poisson {
+    pub fn new(rate: f32) -> Result<Poisson, Error> {
+        // Exp accepts a rate of 0, which would never produce an arrival
+        if rate <= 0.0 || rate.is_nan() {
+            return Err(Error::configuration(format!(
+                "arrival rate {} is not positive",
+                rate
+            )));
+        }
+        Ok(Poisson {
+            gaps: Exp::new(rate).map_err(|e| Error::configuration(e.to_string()))?,
+            time: 0.0,
+        })
+    }
+
+    /// Arrival rate giving offered load ρ = λ * mean burst time on a single CPU
+    pub fn with_load(load: f32, mean_burst_time: f32) -> Result<Poisson, Error> {
+        Poisson::new(load / mean_burst_time)
+    }
+}
+
+impl ArrivalProcess for Poisson {
+    fn next_arrival(&mut self, rng: &mut StdRng) -> Option<f32> {
+        self.time += self.gaps.sample(rng);
+        Some(self.time)
+    }
+}
+
+/// Two-state Markov-modulated Poisson process. Arrivals come with `on_rate`
+/// during bursts and `off_rate` (possibly 0) in between, and both periods
+/// last an exponentially distributed time with given means.
+pub struct OnOffBursts {
+    on_rate: f32,
+    off_rate: f32,
+    mean_on_duration: f32,
+    mean_off_duration: f32,
+    on: bool,
+    time: f32,
+    /// End of the current period
+    switch_time: Option<f32>,
+}
+
+impl OnOffBursts {
+    pub fn new(
+        on_rate: f32,
+        off_rate: f32,
+        mean_on_duration: f32,
+        mean_off_duration: f32,
+    ) -> Result<OnOffBursts, Error> {
+        let valid = on_rate >= 0.0
+            && off_rate >= 0.0
+            && on_rate + off_rate > 0.0
+            && mean_on_duration > 0.0
+            && mean_off_duration > 0.0;
+        if !valid {
+            return Err(Error::configuration(format!(
+                "on/off arrival rates ({}, {}) or mean durations ({}, {}) out of range",
+                on_rate, off_rate, mean_on_duration, mean_off_duration
+            )));
+        }
+        Ok(OnOffBursts {
+            on_rate,
+            off_rate,
+            mean_on_duration,
+            mean_off_duration,
+            on: true,
+            time: 0.0,
+            switch_time: None,
+        })
+    }
+
+    pub fn on_rate(&self) -> f32 {
+        self.on_rate
+    }
+
+    pub fn off_rate(&self) -> f32 {
+        self.off_rate
+    }
+
+    pub fn mean_on_duration(&self) -> f32 {
+        self.mean_on_duration
+    }
+
+    pub fn mean_off_duration(&self) -> f32 {
+        self.mean_off_duration
+    }
+
+    /// Long-run average arrival rate
+    pub fn average_rate(&self) -> f32 {
+        (self.on_rate * self.mean_on_duration + self.off_rate * self.mean_off_duration)
+            / (self.mean_on_duration + self.mean_off_duration)
+    }
+}
+
+impl ArrivalProcess for OnOffBursts {
+    fn next_arrival(&mut self, rng: &mut StdRng) -> Option<f32> {
+        loop {
+            let (rate, mean_duration) = match self.on {
+                true => (self.on_rate, self.mean_on_duration),
+                false => (self.off_rate, self.mean_off_duration),
+            };
+            let switch_time = *self.switch_time.get_or_insert_with(|| {
+                // Parameters are checked by the constructor
+                self.time + Exp::new(1.0 / mean_duration).unwrap().sample(rng)
+            });
+            // Exponential gaps are memoryless, so the gap can be drawn again after a switch
+            let next_arrival = match rate > 0.0 {
+                true => self.time + Exp::new(rate).unwrap().sample(rng),
+                false => f32::INFINITY,
+            };
+            if next_arrival <= switch_time {
+                self.time = next_arrival;
+                return Some(self.time);
+            }
+            self.time = switch_time;
+            self.on = !self.on;
+            self.switch_time = None;
+        }
+    }
+}
+
+/// Arrivals every `period` time units, each one shifted
+/// by a uniformly distributed jitter in [-jitter, jitter]
+pub struct Periodic {
+    period: f32,
+    jitter: f32,
+    arrivals_count: usize,
+    last_arrival: f32,
+}
+
+impl Periodic {
+    pub fn new(period: f32, jitter: f32) -> Result<Periodic, Error> {
+        let valid = period.is_finite() && period > 0.0 && jitter.is_finite() && jitter >= 0.0;
+        if !valid {
+            return Err(Error::configuration(format!(
+                "arrival period {} or jitter {} out of range",
+                period, jitter
+            )));
+        }
+        Ok(Periodic {
+            period,
+            jitter,
+            arrivals_count: 0,
+            last_arrival: 0.0,
+        })
+    }
+
+    pub fn period(&self) -> f32 {
+        self.period
+    }
+
+    pub fn jitter(&self) -> f32 {
+        self.jitter
+    }
+}
+
+impl ArrivalProcess for Periodic {
+    fn next_arrival(&mut self, rng: &mut StdRng) -> Option<f32> {
+        let scheduled = self.arrivals_count as f32 * self.period;
+        self.arrivals_count += 1;
+        let jitter = match self.jitter > 0.0 {
+            true => rng.random_range(-self.jitter..=self.jitter),
+            false => 0.0,
+        };
+        // Jitter never reorders arrivals or moves them before time 0
+        self.last_arrival = (scheduled + jitter).max(self.last_arrival).max(0.0);
+        Some(self.last_arrival)
+    }
+}
+
+/// Replays recorded arrival times, in ascending order
+pub struct ArrivalTrace {
+    arrival_times: Vec<f32>,
+    position: usize,
+}
+
+impl ArrivalTrace {
+    pub fn new(mut arrival_times: Vec<f32>) -> ArrivalTrace {
+        arrival_times.sort_by(|a, b| a.total_cmp(b));
+        ArrivalTrace {
+            arrival_times,
+            position: 0,
+        }
+    }
+
+    /// Reads a JSON list of arrival times
+    pub fn read_from(path: &str) -> Result<ArrivalTrace, Error> {
+        Ok(ArrivalTrace::new(read_json_from(path)?))
+    }
+}
+
+impl ArrivalProcess for ArrivalTrace {
+    fn next_arrival(&mut self, _rng: &mut StdRng) -> Option<f32> {
+        let arrival = self.arrival_times.get(self.position).copied();
+        self.position += 1;
+        arrival
+    }
+}
+
+#[cfg(test)]
+mod arrivals_tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn arrivals(process: &mut dyn ArrivalProcess, count: usize) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..count)
+            .map_while(|_| process.next_arrival(&mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn test_poisson_rate() {
+        let times = arrivals(&mut Poisson::with_load(0.5, 10.0).unwrap(), 10_000);
+        assert!(times.windows(2).all(|x| x[0] <= x[1]));
+        // 0.05 arrivals per time unit
+        let rate = times.len() as f32 / times.last().unwrap();
+        assert!((0.045..0.055).contains(&rate));
+    }
+
+    #[test]
+    fn test_on_off_bursts() {
+        let mut bursts = OnOffBursts::new(1.0, 0.0, 50.0, 150.0).unwrap();
+        assert!(bursts.average_rate() == 0.25);
+        let times = arrivals(&mut bursts, 10_000);
+        let rate = times.len() as f32 / times.last().unwrap();
+        assert!((0.2..0.3).contains(&rate));
+        // Silent periods show up as gaps much longer than the ones within bursts
+        let long_gaps = times.windows(2).filter(|x| x[1] - x[0] > 20.0).count();
+        assert!(long_gaps > 10);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(Poisson::new(0.0).is_err());
+        assert!(Poisson::with_load(0.5, -1.0).is_err());
+        assert!(UniformSteps::new(1.5).is_err());
+        assert!(OnOffBursts::new(0.0, 0.0, 1.0, 1.0).is_err());
+        assert!(OnOffBursts::new(1.0, 0.0, 1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_periodic() {
+        assert!(arrivals(&mut Periodic::new(5.0, 0.0).unwrap(), 3) == vec![0.0, 5.0, 10.0]);
+        let times = arrivals(&mut Periodic::new(5.0, 1.0).unwrap(), 100);
+        for (i, time) in times.iter().enumerate().skip(1) {
+            assert!((time - i as f32 * 5.0).abs() <= 1.0);
+        }
+        assert!(Periodic::new(0.0, 1.0).is_err());
+        assert!(Periodic::new(5.0, -1.0).is_err());
+        assert!(Periodic::new(5.0, f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_arrival_trace() {
+        let mut trace = ArrivalTrace::new(vec![3.0, 1.0, 2.0]);
+        assert!(arrivals(&mut trace, 10) == vec![1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt::Display;
+
+use rand::{distr::weighted::WeightedIndex, rngs::StdRng};
+use rand_distr::{Distribution, Exp, LogNormal, Normal, Pareto};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Draws after which a truncated normal distribution gives up, which only
+/// happens in practice when `min` lies over ~3.5 deviations above the mean
+const TRUNCATED_NORMAL_ATTEMPTS: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "distribution", rename_all = "snake_case", deny_unknown_fields)]
+/// Distribution burst times are sampled from
+pub enum BurstDistribution {
+    /// Normal distribution with negative samples mirrored, as `generate_queue`
+    /// has always done. Skews the distribution if the mean is close to 0.
+    FoldedNormal {
+        mean: f32,
+        standard_deviation: f32,
+    },
+    /// Normal distribution with samples below `min` drawn again,
+    /// `min` can't be negative
+    TruncatedNormal {
+        mean: f32,
+        standard_deviation: f32,
+        min: f32,
+    },
+    Exponential {
+        mean: f32,
+    },
+    /// Log-normal distribution with given mean and standard deviation
+    /// of the burst times themselves (not of their logarithm)
+    LogNormal {
+        mean: f32,
+        standard_deviation: f32,
+    },
+    /// Heavy-tailed distribution, bursts are at least `scale` long.
+    /// The lower the shape, the heavier the tail (infinite mean for shape <= 1).
+    Pareto {
+        scale: f32,
+        shape: f32,
+    },
+    /// Mixture of exponential distributions, given as (probability, mean) pairs
+    Hyperexponential {
+        phases: Vec<(f32, f32)>,
+    },
+    /// Mixture of distributions, given as (weight, distribution) pairs.
+    /// A bimodal distribution is a mixture of two normal distributions.
+    Mixture {
+        components: Vec<(f32, BurstDistribution)>,
+    },
+}
+
+impl BurstDistribution {
+    /// Fails if the parameters don't describe a valid distribution,
+    /// e.g. a negative standard deviation
+    pub fn sample(&self, rng: &mut StdRng) -> Result<f32, Error> {
+        Ok(match self {
+            BurstDistribution::FoldedNormal {
+                mean,
+                standard_deviation,
+            } => normal(self, *mean, *standard_deviation)?.sample(rng).abs(),
+            BurstDistribution::TruncatedNormal {
+                mean,
+                standard_deviation,
+                min,
+            } => {
+                if !(mean.is_finite() && min.is_finite() && *min >= 0.0) {
+                    return Err(invalid(self, "mean or min out of range"));
+                }
+                let normal = normal(self, *mean, *standard_deviation)?;
+                if *standard_deviation == 0.0 && mean < min {
+                    return Err(invalid(self, "mean below min without any deviation"));
+                }
+                (0..TRUNCATED_NORMAL_ATTEMPTS)
+                    .map(|_| normal.sample(rng))
+                    .find(|x| x >= min)
+                    .ok_or_else(|| invalid(self, "min lies too far in the tail"))?
+            }
+            BurstDistribution::Exponential { mean } => Exp::new(1.0 / mean)
+                .map_err(|e| invalid(self, e))?
+                .sample(rng),
+            BurstDistribution::LogNormal {
+                mean,
+                standard_deviation,
+            } => {
+                let sigma_squared = (1.0 + (standard_deviation / mean).powi(2)).ln();
+                let mu = mean.ln() - sigma_squared / 2.0;
+                LogNormal::new(mu, sigma_squared.sqrt())
+                    .map_err(|e| invalid(self, e))?
+                    .sample(rng)
+            }
+            BurstDistribution::Pareto { scale, shape } => Pareto::new(*scale, *shape)
+                .map_err(|e| invalid(self, e))?
+                .sample(rng),
+            BurstDistribution::Hyperexponential { phases } => {
+                let phase = WeightedIndex::new(phases.iter().map(|x| x.0))
+                    .map_err(|e| invalid(self, e))?
+                    .sample(rng);
+                Exp::new(1.0 / phases[phase].1)
+                    .map_err(|e| invalid(self, e))?
+                    .sample(rng)
+            }
+            BurstDistribution::Mixture { components } => {
+                let component = WeightedIndex::new(components.iter().map(|x| x.0))
+                    .map_err(|e| invalid(self, e))?
+                    .sample(rng);
+                components[component].1.sample(rng)?
+            }
+        })
+    }
+
+    /// Expected burst time. Folded and truncated normal distributions
+    /// report the mean of the underlying normal distribution.
+    pub fn mean(&self) -> f32 {
+        match self {
+            BurstDistribution::FoldedNormal { mean, .. }
+            | BurstDistribution::TruncatedNormal { mean, .. }
+            | BurstDistribution::Exponential { mean }
+            | BurstDistribution::LogNormal { mean, .. } => *mean,
+            BurstDistribution::Pareto { scale, shape } => match *shape > 1.0 {
+                true => shape * scale / (shape - 1.0),
+                false => f32::INFINITY,
+            },
+            BurstDistribution::Hyperexponential { phases } => {
+                let total: f32 = phases.iter().map(|x| x.0).sum();
+                phases.iter().map(|(p, mean)| p * mean).sum::<f32>() / total
+            }
+            BurstDistribution::Mixture { components } => {
+                let total: f32 = components.iter().map(|x| x.0).sum();
+                components
+                    .iter()
+                    .map(|(weight, distribution)| weight * distribution.mean())
+                    .sum::<f32>()
+                    / total
+            }
+        }
+    }
+}
+
+/// `Normal::new` accepts negative standard deviations
+fn normal(
+    distribution: &BurstDistribution,
+    mean: f32,
+    standard_deviation: f32,
+) -> Result<Normal<f32>, Error> {
+    if standard_deviation < 0.0 {
+        return Err(invalid(distribution, "negative standard deviation"));
+    }
+    Normal::new(mean, standard_deviation).map_err(|e| invalid(distribution, e))
+}
+
+fn invalid(distribution: &BurstDistribution, error: impl Display) -> Error {
+    Error::configuration(format!("burst distribution {:?}: {}", distribution, error))
+}
+
+#[cfg(test)]
+mod bursts_tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn sample_mean(distribution: &BurstDistribution) -> f32 {
+        let mut rng = StdRng::seed_from_u64(1);
+        let samples: Vec<f32> = (0..50_000)
+            .map(|_| distribution.sample(&mut rng).unwrap())
+            .collect();
+        assert!(samples.iter().all(|x| *x >= 0.0));
+        samples.iter().sum::<f32>() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_sample_means() {
+        let distributions = vec![
+            BurstDistribution::Exponential { mean: 10.0 },
+            BurstDistribution::LogNormal {
+                mean: 10.0,
+                standard_deviation: 20.0,
+            },
+            BurstDistribution::Pareto {
+                scale: 5.0,
+                shape: 3.0,
+            },
+            BurstDistribution::Hyperexponential {
+                phases: vec![(0.9, 5.0), (0.1, 55.0)],
+            },
+            BurstDistribution::Mixture {
+                components: vec![
+                    (
+                        3.0,
+                        BurstDistribution::TruncatedNormal {
+                            mean: 2.0,
+                            standard_deviation: 0.5,
+                            min: 0.0,
+                        },
+                    ),
+                    (1.0, BurstDistribution::Exponential { mean: 34.0 }),
+                ],
+            },
+        ];
+        for distribution in distributions {
+            assert!(distribution.mean() == 7.5 || distribution.mean() == 10.0);
+            let mean = sample_mean(&distribution);
+            assert!((mean - distribution.mean()).abs() < distribution.mean() * 0.1);
+        }
+    }
+
+    #[test]
+    fn test_truncated_normal() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let distribution = BurstDistribution::TruncatedNormal {
+            mean: 1.0,
+            standard_deviation: 5.0,
+            min: 0.5,
+        };
+        assert!((0..1000).all(|_| distribution.sample(&mut rng).unwrap() >= 0.5));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let distribution = BurstDistribution::FoldedNormal {
+            mean: 5.0,
+            standard_deviation: -1.0,
+        };
+        let error = distribution.sample(&mut rng).unwrap_err();
+        assert!(matches!(error, Error::Configuration(_)));
+        let distribution = BurstDistribution::TruncatedNormal {
+            mean: 5.0,
+            standard_deviation: f32::NAN,
+            min: 0.0,
+        };
+        assert!(distribution.sample(&mut rng).is_err());
+        for (standard_deviation, min) in [(-1.0, 0.0), (1.0, f32::NAN), (1.0, -1.0), (1.0, 50.0)] {
+            let truncated = BurstDistribution::TruncatedNormal {
+                mean: 5.0,
+                standard_deviation,
+                min,
+            };
+            assert!(truncated.sample(&mut rng).is_err());
+        }
+        assert!(BurstDistribution::Exponential { mean: -2.0 }
+            .sample(&mut rng)
+            .is_err());
+        let mixture = BurstDistribution::Mixture {
+            components: vec![(1.0, distribution)],
+        };
+        assert!(mixture.sample(&mut rng).is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let distribution: BurstDistribution =
+            serde_json::from_str(r#"{"distribution": "pareto", "scale": 1.0, "shape": 1.5}"#)
+                .unwrap();
+        assert!(
+            distribution
+                == BurstDistribution::Pareto {
+                    scale: 1.0,
+                    shape: 1.5
+                }
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use super::ProcessSpec;
+
+/// Lifecycle of a process during a simulation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProcessState {
+    /// Not arrived yet
+    New,
+    /// Arrived and waiting for the CPU
+    Ready,
+    Running,
+    /// Blocked, e.g. on I/O, and not competing for the CPU
+    Waiting,
+    Terminated,
+}
+
+impl ProcessState {
+    /// Whether a process can go straight from this state to `next`
+    pub fn can_become(self, next: ProcessState) -> bool {
+        use ProcessState::*;
+        matches!(
+            (self, next),
+            (New, Ready)
+                | (Ready, Running)
+                | (Running, Ready)
+                | (Running, Waiting)
+                | (Running, Terminated)
+                | (Waiting, Ready)
+        )
+    }
+}
+
+/// Runtime state of a process, kept by the simulations next to
+/// the spec it was created from, which is left untouched
+pub struct ProcessControlBlock<'a> {
+    pub spec: &'a ProcessSpec,
+    state: ProcessState,
+    /// Burst time left to run
+    remaining_time: f32,
+    /// Last time the process became ready
+    ready_since: f32,
+    /// Time spent ready but not running so far
+    waiting_time: f32,
+    finish_time: Option<f32>,
+}
+
+impl<'a> ProcessControlBlock<'a> {
+    pub fn new(spec: &'a ProcessSpec) -> ProcessControlBlock<'a> {
+        ProcessControlBlock {
+            spec,
+            state: ProcessState::New,
+            remaining_time: spec.burst_time,
+            ready_since: spec.arrival_time,
+            waiting_time: 0.0,
+            finish_time: None,
+        }
+    }
+
+    pub fn state(&self) -> ProcessState {
+        self.state
+    }
+
+    pub fn remaining_time(&self) -> f32 {
+        self.remaining_time
+    }
+
+    pub fn waiting_time(&self) -> f32 {
+        self.waiting_time
+    }
+
+    pub fn finish_time(&self) -> Option<f32> {
+        self.finish_time
+    }
+
+    /// Makes the process ready, it counts as waiting from its arrival time on
+    /// even if the simulation only notices its arrival later
+    pub fn admit(&mut self) {
+        self.transition(ProcessState::Ready);
+        self.ready_since = self.spec.arrival_time;
+    }
+
+    /// Gives the CPU to the process at `time`
+    pub fn dispatch(&mut self, time: f32) {
+        self.transition(ProcessState::Running);
+        self.waiting_time += time - self.ready_since;
+    }
+
+    /// Takes the CPU back at `time`, after the process ran for `processed_for`
+    pub fn preempt(&mut self, time: f32, processed_for: f32) {
+        self.transition(ProcessState::Ready);
+        self.remaining_time -= processed_for;
+        self.ready_since = time;
+    }
+
+    /// The process stops competing for the CPU after running for `processed_for`
+    pub fn block(&mut self, processed_for: f32) {
+        self.transition(ProcessState::Waiting);
+        self.remaining_time -= processed_for;
+    }
+
+    /// The process is ready again at `time`
+    pub fn wake(&mut self, time: f32) {
+        self.transition(ProcessState::Ready);
+        self.ready_since = time;
+    }
+
+    /// The process finished its burst at `time`
+    pub fn terminate(&mut self, time: f32) {
+        self.transition(ProcessState::Terminated);
+        self.remaining_time = 0.0;
+        self.finish_time = Some(time);
+    }
+
+    /// Panics on transitions the state machine doesn't allow,
+    /// which can only come from a broken simulation
+    fn transition(&mut self, next: ProcessState) {
+        assert!(
+            self.state.can_become(next),
+            "process {} can't go from {:?} to {:?}",
+            self.spec.name,
+            self.state,
+            next
+        );
+        self.state = next;
+    }
+}
+
+#[cfg(test)]
+mod control_block_tests {
+    use super::*;
+    use crate::process::create;
+
+    #[test]
+    fn test_lifecycle() {
+        let spec = create("A", 1.0, 5.0, None);
+        let mut block = ProcessControlBlock::new(&spec);
+        assert!(block.state() == ProcessState::New);
+        block.admit();
+        block.dispatch(3.0);
+        block.preempt(5.0, 2.0);
+        assert!(block.state() == ProcessState::Ready && block.remaining_time() == 3.0);
+        block.dispatch(6.0);
+        block.block(1.0);
+        block.wake(10.0);
+        block.dispatch(10.0);
+        block.terminate(12.0);
+        assert!(block.state() == ProcessState::Terminated);
+        assert!(block.waiting_time() == 3.0 && block.finish_time() == Some(12.0));
+        // The spec keeps its burst time
+        assert!(spec.burst_time == 5.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't go from New to Running")]
+    fn test_invalid_transition() {
+        let spec = create("A", 0.0, 1.0, None);
+        ProcessControlBlock::new(&spec).dispatch(0.0);
+    }
+}
@@ -3,96 +3,174 @@ use std::{
     io::Write,
 };
 
-use rand::{random_bool, Rng};
-use rand_distr::{Distribution, Normal};
-use serde::{Deserialize, Serialize};
-use serde_json::Error;
+use arrivals::{ArrivalProcess, UniformSteps};
+use bursts::BurstDistribution;
+use rand::{rngs::StdRng, Rng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    validation::{fix_processes, validate_processes, Problem},
+};
+
+pub mod arrivals;
+pub mod bursts;
+pub mod control_block;
+#[cfg(target_os = "linux")]
+pub mod procfs;
+pub mod swf;
 
 #[derive(Serialize, Deserialize, Clone)]
-/// Representation of a process to be used in
-/// scheduling algorithm simulations
-pub struct Process {
+/// Description of a process to be used in scheduling algorithm simulations,
+/// as read from and written to input files. Simulations never modify it and
+/// keep their own state in a `ProcessControlBlock` instead. Files written
+/// by older versions also hold `waiting_since` and `total_waiting_time`
+/// fields, they are ignored.
+pub struct ProcessSpec {
     pub arrival_time: f32,
     pub burst_time: f32,
     pub priority: Option<u16>,
     pub name: String,
-    pub waiting_since: Option<f32>,
-    pub total_waiting_time: Option<f32>,
+    /// Pages referenced over the course of the burst, evenly spread
+    /// across it. Only used by virtual memory simulations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_references: Option<Vec<u64>>,
+    /// Batch job details, only set for jobs imported from workload logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job: Option<swf::JobInfo>,
+    /// I/O the process waits for over the course of its burst,
+    /// in the order it's requested. Not simulated by the schedulers yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_requests: Option<Vec<IoRequest>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// A blocking I/O operation requested by a process
+pub struct IoRequest {
+    /// CPU time the process got before the request
+    pub cpu_time: f32,
+    /// Time the process waits for the operation to complete
+    pub duration: f32,
 }
 
 /// A de-facto process constructor
-pub fn create(name: &str, arrival_time: f32, burst_time: f32, priority: Option<u16>) -> Process {
-    Process {
+pub fn create(
+    name: &str,
+    arrival_time: f32,
+    burst_time: f32,
+    priority: Option<u16>,
+) -> ProcessSpec {
+    ProcessSpec {
         arrival_time,
         priority,
         burst_time,
         name: name.to_owned(),
-        waiting_since: None,
-        total_waiting_time: None,
+        page_references: None,
+        job: None,
+        io_requests: None,
     }
 }
 
 ///// A bunch of serialization/deserialization utility functions
 
-pub fn read_processes_list_from(path: &str) -> Result<Vec<Process>, Error> {
-    let path_content = fs::read_to_string(path).unwrap();
-    let queue: Vec<Process> = serde_json::from_str(&path_content).unwrap();
+/// Fails if the list doesn't pass `validation::validate_processes`
+pub fn read_processes_list_from(path: &str) -> Result<Vec<ProcessSpec>, Error> {
+    let queue: Vec<ProcessSpec> = read_json_from(path)?;
+    validate_processes(&queue).map_err(|error| validation_in_file(error, path))?;
     Ok(queue)
 }
 
-pub fn save_processes_list_to_path(queue: &Vec<Process>, path: &str) -> Result<(), Error> {
-    let serialized_queue = serde_json::to_string(&queue).unwrap();
-    let mut file = File::create(path).unwrap();
-    file.write_all(serialized_queue.as_bytes()).unwrap();
-    Ok(())
+/// Same as `read_processes_list_from`, but sorts the list and renames
+/// duplicates with `validation::fix_processes` instead of failing.
+/// Returns the problems that were fixed along with the list.
+pub fn read_and_fix_processes_list_from(
+    path: &str,
+) -> Result<(Vec<ProcessSpec>, Vec<Problem>), Error> {
+    let mut queue: Vec<ProcessSpec> = read_json_from(path)?;
+    let problems = fix_processes(&mut queue).map_err(|error| validation_in_file(error, path))?;
+    Ok((queue, problems))
 }
 
-pub fn read_pages_references_from(path: &str) -> Result<Vec<i16>, Error> {
-    let path_content = fs::read_to_string(path).unwrap();
-    let queue: Vec<i16> = serde_json::from_str(&path_content).unwrap();
-    Ok(queue)
+/// Validation errors don't carry a path, the file is named in the message instead
+fn validation_in_file(error: Error, path: &str) -> Error {
+    match error {
+        Error::Validation(message) => Error::validation(format!("{}: {}", path, message)),
+        error => error,
+    }
+}
+
+pub fn save_processes_list_to_path(queue: &Vec<ProcessSpec>, path: &str) -> Result<(), Error> {
+    save_json_to_path(queue, path)
+}
+
+pub fn read_pages_references_from(path: &str) -> Result<Vec<u64>, Error> {
+    read_json_from(path)
+}
+
+pub fn save_pages_references_to_path(references: Vec<u64>, path: &str) -> Result<(), Error> {
+    save_json_to_path(&references, path)
+}
+
+/// Errors mention the path, and the line for parse errors
+pub(crate) fn read_json_from<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
+    let read = || -> Result<T, Error> { Ok(serde_json::from_str(&fs::read_to_string(path)?)?) };
+    read().map_err(|error| error.in_file(path))
 }
 
-pub fn save_pages_references_to_path(references: Vec<i16>, path: &str) -> Result<(), Error> {
-    let serialized_references = serde_json::to_string(&references).unwrap();
-    let mut file = File::create(path).unwrap();
-    file.write_all(serialized_references.as_bytes()).unwrap();
-    Ok(())
+pub(crate) fn save_json_to_path<T: Serialize + ?Sized>(value: &T, path: &str) -> Result<(), Error> {
+    let write = || -> Result<(), Error> {
+        let serialized = serde_json::to_string(value)?;
+        File::create(path)?.write_all(serialized.as_bytes())?;
+        Ok(())
+    };
+    write().map_err(|error| error.in_file(path))
 }
 
 /// Returns a list of processes with burst times meeting
-/// particular standard deviation parameters
+/// particular standard deviation parameters.
+/// All randomness comes from `rng`, so a seeded generator gives the same queue every time
 pub fn generate_queue(
     length: i16,
     mean_burst_time: f32,
     burst_time_standard_deviation: f32,
-) -> Vec<Process> {
-    let mut queue: Vec<Process> = vec![];
-    let mut rng = rand::rng();
-    let mut time: f32 = 0.0;
-    let normal = Normal::new(mean_burst_time, burst_time_standard_deviation).unwrap();
+    rng: &mut StdRng,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let bursts = BurstDistribution::FoldedNormal {
+        mean: mean_burst_time,
+        standard_deviation: burst_time_standard_deviation,
+    };
+    generate_queue_with_arrivals(length, &bursts, &mut UniformSteps::default(), rng)
+}
+
+/// Same as `generate_queue`, but with burst times sampled from a given
+/// distribution and arrival times coming from a given arrival process.
+/// The queue is shorter than `length` if the arrival process runs out of arrivals.
+pub fn generate_queue_with_arrivals(
+    length: i16,
+    bursts: &BurstDistribution,
+    arrivals: &mut dyn ArrivalProcess,
+    rng: &mut StdRng,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let mut queue: Vec<ProcessSpec> = vec![];
 
     for i in 0..length {
-        let mut burst_time = normal.sample(&mut rand::rng());
-        if burst_time < 0.0 {
-            burst_time *= -1.0;
-        }
+        let burst_time = bursts.sample(rng)?;
         let priority: u16 = rng.random();
-        let time_progression = random_bool(0.9);
-        if time_progression {
-            let random_time_progression: f32 = rng.random();
-            time += random_time_progression;
-        }
-        queue.push(Process {
-            arrival_time: time,
-            burst_time: burst_time,
+        let Some(arrival_time) = arrivals.next_arrival(rng) else {
+            break;
+        };
+        queue.push(ProcessSpec {
+            arrival_time,
+            burst_time,
             priority: Some(priority),
             name: get_human_readable_process_name(i),
-            waiting_since: None,
-            total_waiting_time: None,
+            page_references: None,
+            job: None,
+            io_requests: None,
         });
     }
-    queue
+    validate_processes(&queue)?;
+    Ok(queue)
 }
 
 /// I thought that names "Process_1", "Process_2", etc. would not be
@@ -109,3 +187,69 @@ fn get_human_readable_process_name(process_number: i16) -> String {
     let name = format!("{}_{}", nato_phonetic_alphabet.get(index).unwrap(), suffix);
     name.to_owned()
 }
+
+#[cfg(test)]
+mod process_tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_read_processes_list() {
+        let queue = read_processes_list_from("test_data/convoy_ascending_input.json").unwrap();
+        assert!(!queue.is_empty());
+
+        let error = read_processes_list_from("test_data/missing.json")
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::Io { .. }));
+        assert!(error.to_string().starts_with("test_data/missing.json: "));
+
+        let path = env::temp_dir().join("scheduling_project_malformed_queue.json");
+        let path = path.to_str().unwrap();
+        fs::write(
+            path,
+            "[\n{\"arrival_time\": 0.0,\n\"burst_time\": \"long\"}]",
+        )
+        .unwrap();
+        let error = read_processes_list_from(path).err().unwrap();
+        assert!(matches!(error, Error::Parse { line: Some(3), .. }));
+        assert!(error
+            .to_string()
+            .starts_with(&format!("{}: line 3: ", path)));
+
+        fs::write(
+            path,
+            r#"[{"arrival_time": 2.0, "burst_time": 1.0, "priority": null, "name": "A"},
+            {"arrival_time": 1.0, "burst_time": 1.0, "priority": null, "name": "A"}]"#,
+        )
+        .unwrap();
+        let error = read_processes_list_from(path).err().unwrap();
+        assert!(matches!(error, Error::Validation(_)));
+        assert!(error.to_string().contains("2 problem(s)"));
+        let (queue, problems) = read_and_fix_processes_list_from(path).unwrap();
+        assert!(problems.len() == 2);
+        assert!(queue[0].arrival_time == 1.0 && queue[1].name == "A_2");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_legacy_processes_list() {
+        // Written before runtime state moved out of the spec
+        let path = env::temp_dir().join("scheduling_project_legacy_queue.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, r#"[{"arrival_time": 0.0, "burst_time": 4.0, "priority": 7, "name": "A", "waiting_since": null, "total_waiting_time": null},
+            {"arrival_time": 1.0, "burst_time": 2.0, "priority": null, "name": "B", "waiting_since": 3.0, "total_waiting_time": 1.5}]"#).unwrap();
+        let queue = read_processes_list_from(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert!(queue.len() == 2 && queue[1].name == "B" && queue[1].burst_time == 2.0);
+        assert!(queue[0].priority == Some(7));
+
+        // A file the experiments wrote back then, kept as is
+        let path = "test_data/legacy_processes.json";
+        assert!(fs::read_to_string(path)
+            .unwrap()
+            .contains("total_waiting_time"));
+        assert!(read_processes_list_from(path).unwrap().len() == 50);
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use super::{create, ProcessSpec};
+use crate::{error::Error, validation::validate_processes};
+
+/// "No such process", returned when reading the files of a process
+/// that exits in the middle of the read (from errno.h, libc isn't a dependency)
+const ESRCH: i32 = 3;
+
+/// Fields of `/proc/[pid]/stat` used to rebuild a process
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProcStat {
+    pub pid: u32,
+    /// Executable name, truncated to 15 characters by the kernel
+    pub command: String,
+    /// R (running), S (sleeping), D (disk sleep), Z (zombie), ...
+    pub state: char,
+    pub parent_pid: u32,
+    /// Clock ticks spent in user and kernel mode
+    pub user_time: u64,
+    pub system_time: u64,
+    pub nice: i32,
+    /// Clock ticks between boot and the start of the process
+    pub start_time: u64,
+}
+
+/// Contents of `/proc/[pid]/schedstat`, only available
+/// on kernels built with scheduler statistics
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SchedStat {
+    /// Nanoseconds spent on a CPU
+    pub run_time: u64,
+    /// Nanoseconds spent waiting on a run queue
+    pub wait_time: u64,
+    /// Number of times the process was scheduled in
+    pub timeslices: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProcSnapshot {
+    pub stat: ProcStat,
+    pub schedstat: Option<SchedStat>,
+}
+
+pub struct ProcfsConfig {
+    /// `getconf CLK_TCK`, 100 on virtually every Linux system
+    pub clock_ticks_per_second: f32,
+    /// Kernel threads (children of kthreadd) are skipped unless set
+    pub include_kernel_threads: bool,
+}
+
+impl Default for ProcfsConfig {
+    fn default() -> Self {
+        ProcfsConfig {
+            clock_ticks_per_second: 100.0,
+            include_kernel_threads: false,
+        }
+    }
+}
+
+impl ProcSnapshot {
+    /// Accumulated CPU time in seconds, from schedstat when
+    /// available as it is more precise than clock ticks
+    pub fn cpu_time(&self, config: &ProcfsConfig) -> f32 {
+        match self.schedstat {
+            Some(schedstat) => schedstat.run_time as f32 / 1e9,
+            None => {
+                (self.stat.user_time + self.stat.system_time) as f32 / config.clock_ticks_per_second
+            }
+        }
+    }
+
+    fn is_kernel_thread(&self) -> bool {
+        self.stat.pid == 2 || self.stat.parent_pid == 2
+    }
+}
+
+/// Maps a nice value (-20 to 19) to a priority (0 to 39),
+/// lower priorities being more urgent as in the kernel
+pub fn nice_to_priority(nice: i32) -> u16 {
+    (nice.clamp(-20, 19) + 20) as u16
+}
+
+pub fn priority_to_nice(priority: u16) -> i32 {
+    priority.min(39) as i32 - 20
+}
+
+pub fn parse_stat(content: &str) -> Option<ProcStat> {
+    // The command is in parentheses and may itself contain spaces and parentheses
+    let (pid, rest) = content.split_once(" (")?;
+    let (command, rest) = rest.rsplit_once(") ")?;
+    // Fields numbered as in proc(5), starting with the state as field 3
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |number: usize| fields.get(number - 3).copied();
+    Some(ProcStat {
+        pid: pid.trim().parse().ok()?,
+        command: command.to_owned(),
+        state: field(3)?.chars().next()?,
+        parent_pid: field(4)?.parse().ok()?,
+        user_time: field(14)?.parse().ok()?,
+        system_time: field(15)?.parse().ok()?,
+        nice: field(19)?.parse().ok()?,
+        start_time: field(22)?.parse().ok()?,
+    })
+}
+
+pub fn parse_schedstat(content: &str) -> Option<SchedStat> {
+    let fields: Vec<u64> = content
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    match fields[..] {
+        [run_time, wait_time, timeslices] => Some(SchedStat {
+            run_time,
+            wait_time,
+            timeslices,
+        }),
+        _ => None,
+    }
+}
+
+/// Reads every process under a procfs mount, normally `/proc`.
+/// Processes exiting while the snapshot is taken or not visible are left out.
+pub fn read_procfs_snapshot(proc_root: &str) -> Result<Vec<ProcSnapshot>, Error> {
+    let mut snapshots = vec![];
+    let entries = fs::read_dir(proc_root).map_err(|error| Error::from(error).in_file(proc_root))?;
+    for entry in entries {
+        let path = entry?.path();
+        let is_pid = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| x.bytes().all(|x| x.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        if let Some(snapshot) = read_process(&path)? {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots.sort_by_key(|x| x.stat.pid);
+    Ok(snapshots)
+}
+
+/// Turns a snapshot into processes arriving at their start time, relative to the
+/// oldest one, with their accumulated CPU time as burst time and their nice value
+/// as priority. Processes are named after their command and pid.
+pub fn snapshot_to_processes(
+    snapshots: &[ProcSnapshot],
+    config: &ProcfsConfig,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let bursts = snapshots
+        .iter()
+        .filter(|x| config.include_kernel_threads || !x.is_kernel_thread())
+        .map(|x| (x, x.cpu_time(config)))
+        .collect();
+    to_processes(bursts, config)
+}
+
+/// Estimates the average CPU burst of each process from two snapshots, as the
+/// CPU time it got in between divided by the number of times it was scheduled in.
+/// Only processes that ran in between and have scheduler statistics are kept.
+pub fn estimate_bursts(
+    before: &[ProcSnapshot],
+    after: &[ProcSnapshot],
+    config: &ProcfsConfig,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let mut bursts = vec![];
+    for snapshot in after {
+        if !config.include_kernel_threads && snapshot.is_kernel_thread() {
+            continue;
+        }
+        // A reused pid has a different start time
+        let Some(previous) = before.iter().find(|x| {
+            x.stat.pid == snapshot.stat.pid && x.stat.start_time == snapshot.stat.start_time
+        }) else {
+            continue;
+        };
+        let (Some(start), Some(end)) = (previous.schedstat, snapshot.schedstat) else {
+            continue;
+        };
+        let timeslices = end.timeslices.saturating_sub(start.timeslices);
+        if timeslices == 0 {
+            continue;
+        }
+        let run_time = end.run_time.saturating_sub(start.run_time) as f32 / 1e9;
+        bursts.push((snapshot, run_time / timeslices as f32));
+    }
+    to_processes(bursts, config)
+}
+
+/// Takes two snapshots of `/proc` `interval` apart and estimates bursts from them
+pub fn sample_cpu_bursts(
+    interval: Duration,
+    config: &ProcfsConfig,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let before = read_procfs_snapshot("/proc")?;
+    thread::sleep(interval);
+    let after = read_procfs_snapshot("/proc")?;
+    estimate_bursts(&before, &after, config)
+}
+
+/// None if the process can't be read, it exited in the meantime
+/// or is hidden by the hidepid mount option
+fn read_process(path: &Path) -> Result<Option<ProcSnapshot>, Error> {
+    let read = |file: &str| -> Result<Option<String>, Error> {
+        let path = path.join(file);
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(error) if is_vanished(&error) => Ok(None),
+            Err(error) => Err(Error::from(error).in_file(&path.display().to_string())),
+        }
+    };
+    let Some(stat) = read("stat")? else {
+        return Ok(None);
+    };
+    let stat = parse_stat(&stat).ok_or_else(|| {
+        Error::parse(None, "malformed stat").in_file(&path.join("stat").display().to_string())
+    })?;
+    let schedstat = read("schedstat")?.and_then(|x| parse_schedstat(&x));
+    Ok(Some(ProcSnapshot { stat, schedstat }))
+}
+
+/// Whether a read failed because the process exited or is hidden
+fn is_vanished(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::NotFound | ErrorKind::PermissionDenied
+    ) || error.raw_os_error() == Some(ESRCH)
+}
+
+/// Builds processes out of (snapshot, burst time) pairs
+fn to_processes(
+    bursts: Vec<(&ProcSnapshot, f32)>,
+    config: &ProcfsConfig,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let Some(first_start) = bursts.iter().map(|x| x.0.stat.start_time).min() else {
+        return Ok(vec![]);
+    };
+    let mut queue: Vec<ProcessSpec> = bursts
+        .into_iter()
+        .map(|(snapshot, burst_time)| {
+            create(
+                &format!("{}_{}", snapshot.stat.command, snapshot.stat.pid),
+                (snapshot.stat.start_time - first_start) as f32 / config.clock_ticks_per_second,
+                burst_time,
+                Some(nice_to_priority(snapshot.stat.nice)),
+            )
+        })
+        .collect();
+    queue.sort_by(|a, b| a.arrival_time.total_cmp(&b.arrival_time));
+    validate_processes(&queue)?;
+    Ok(queue)
+}
+
+#[cfg(test)]
+mod procfs_tests {
+    use super::*;
+
+    const STAT: &str = "4242 (tmux: server (1)) S 1 4242 4242 0 -1 4194560 3093 0 0 0 250 130 0 0 20 0 1 0 1500 11321344 1085 18446744073709551615 1 1 0 0 0 0 0 4096 134301191 0 0 0 17 3 0 0 0 0 0";
+
+    fn snapshot(
+        pid: u32,
+        nice: i32,
+        start_time: u64,
+        schedstat: Option<SchedStat>,
+    ) -> ProcSnapshot {
+        ProcSnapshot {
+            stat: ProcStat {
+                pid,
+                command: "worker".to_owned(),
+                state: 'R',
+                parent_pid: 1,
+                user_time: 300,
+                system_time: 100,
+                nice,
+                start_time,
+            },
+            schedstat,
+        }
+    }
+
+    fn schedstat(run_time: u64, timeslices: u64) -> Option<SchedStat> {
+        Some(SchedStat {
+            run_time,
+            wait_time: 0,
+            timeslices,
+        })
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = parse_stat(STAT).unwrap();
+        assert!(stat.pid == 4242 && stat.command == "tmux: server (1)");
+        assert!(stat.state == 'S' && stat.parent_pid == 1);
+        assert!((stat.user_time, stat.system_time) == (250, 130));
+        assert!(stat.nice == 0 && stat.start_time == 1500);
+        assert!(parse_stat("4242 (truncated) S 1").is_none());
+
+        let schedstat = parse_schedstat("1234567 89 10\n").unwrap();
+        assert!(
+            (
+                schedstat.run_time,
+                schedstat.wait_time,
+                schedstat.timeslices
+            ) == (1234567, 89, 10)
+        );
+        assert!(parse_schedstat("1 2").is_none());
+    }
+
+    #[test]
+    fn test_vanished_processes() {
+        assert!(is_vanished(&io::Error::from_raw_os_error(ESRCH)));
+        assert!(is_vanished(&io::Error::from(ErrorKind::NotFound)));
+        assert!(!is_vanished(&io::Error::from(ErrorKind::InvalidData)));
+    }
+
+    #[test]
+    fn test_snapshot_to_processes() {
+        let snapshots = vec![
+            snapshot(10, 0, 1000, None),
+            snapshot(11, -5, 500, schedstat(2_500_000_000, 40)),
+        ];
+        let queue = snapshot_to_processes(&snapshots, &ProcfsConfig::default()).unwrap();
+        assert!(queue[0].name == "worker_11" && queue[0].arrival_time == 0.0);
+        assert!(queue[0].burst_time == 2.5 && queue[0].priority == Some(15));
+        assert!(queue[1].arrival_time == 5.0 && queue[1].burst_time == 4.0);
+        assert!(queue[1].priority == Some(20));
+    }
+
+    #[test]
+    fn test_estimate_bursts() {
+        let before = vec![
+            snapshot(10, 0, 0, schedstat(1_000_000_000, 10)),
+            snapshot(11, 0, 0, schedstat(0, 0)),
+            snapshot(12, 0, 0, schedstat(0, 0)),
+        ];
+        let after = vec![
+            snapshot(10, 0, 0, schedstat(1_200_000_000, 30)),
+            snapshot(11, 0, 0, schedstat(0, 0)),
+            // pid reused by a new process
+            snapshot(12, 0, 100, schedstat(500_000_000, 5)),
+        ];
+        let queue = estimate_bursts(&before, &after, &ProcfsConfig::default()).unwrap();
+        assert!(queue.len() == 1);
+        assert!((queue[0].burst_time - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_read_own_process() {
+        let snapshots = read_procfs_snapshot("/proc").unwrap();
+        let own = snapshots
+            .iter()
+            .find(|x| x.stat.pid == std::process::id())
+            .unwrap();
+        assert!(own.stat.command.starts_with("scheduling"));
+        assert!(priority_to_nice(nice_to_priority(own.stat.nice)) == own.stat.nice);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{create, ProcessSpec};
+use crate::{error::Error, validation::fix_processes};
+
+/// One job of a log in the Standard Workload Format used by the
+/// Parallel Workloads Archive. Times are in seconds, and fields
+/// the log doesn't provide are set to -1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SwfRecord {
+    pub job_number: i64,
+    pub submit_time: f64,
+    pub wait_time: f64,
+    pub run_time: f64,
+    pub allocated_processors: i64,
+    pub average_cpu_time: f64,
+    /// Kilobytes per processor
+    pub used_memory: f64,
+    pub requested_processors: i64,
+    pub requested_time: f64,
+    pub requested_memory: f64,
+    /// 1 if the job completed, 0 if it failed, 5 if it was cancelled
+    pub status: i64,
+    pub user_id: i64,
+    pub group_id: i64,
+    pub executable_number: i64,
+    pub queue_number: i64,
+    pub partition_number: i64,
+    pub preceding_job_number: i64,
+    pub think_time: f64,
+}
+
+/// Batch job details kept from a workload log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JobInfo {
+    pub job_number: u64,
+    #[serde(default)]
+    pub requested_processors: Option<u32>,
+    #[serde(default)]
+    pub requested_time: Option<f32>,
+    #[serde(default)]
+    pub user_id: Option<u32>,
+    #[serde(default)]
+    pub group_id: Option<u32>,
+}
+
+/// Selects the records of a log to replay
+#[derive(Clone, Copy, Default)]
+pub struct SwfFilter {
+    /// Only keep jobs submitted within [start, end)
+    pub time_window: Option<(f64, f64)>,
+    /// Drop records with a missing or negative submit time,
+    /// run time or processor count
+    pub drop_invalid: bool,
+    /// Drop failed and cancelled jobs
+    pub completed_only: bool,
+}
+
+impl SwfRecord {
+    pub fn is_valid(&self) -> bool {
+        self.submit_time >= 0.0 && self.run_time >= 0.0 && self.processors().is_some()
+    }
+
+    /// Requested processors, allocated ones if the request isn't known
+    pub fn processors(&self) -> Option<u32> {
+        let positive = |field: i64| known(field).filter(|x| *x > 0);
+        positive(self.requested_processors).or_else(|| positive(self.allocated_processors))
+    }
+}
+
+impl SwfFilter {
+    pub fn accepts(&self, record: &SwfRecord) -> bool {
+        if self.drop_invalid && !record.is_valid() {
+            return false;
+        }
+        if self.completed_only && record.status != 1 {
+            return false;
+        }
+        match self.time_window {
+            Some((start, end)) => (start..end).contains(&record.submit_time),
+            None => true,
+        }
+    }
+}
+
+/// Parses a log, skipping blank lines and `;` header comments
+pub fn read_swf<R: BufRead>(reader: R) -> Result<Vec<SwfRecord>, Error> {
+    let mut records = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let record = parse_swf_line(line)
+            .ok_or_else(|| Error::parse(Some(i + 1), format!("malformed SWF record '{}'", line)))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn read_swf_from(path: &str) -> Result<Vec<SwfRecord>, Error> {
+    let read = || read_swf(BufReader::new(File::open(path)?));
+    read().map_err(|error| error.in_file(path))
+}
+
+/// Turns the records accepted by the filter into processes arriving at their
+/// submit time, shifted so that the first one arrives at 0, and running for
+/// their run time. Jobs are named after their job number, jobs sharing
+/// a number are renamed by `validation::fix_processes`.
+pub fn swf_to_processes(
+    records: &[SwfRecord],
+    filter: &SwfFilter,
+) -> Result<Vec<ProcessSpec>, Error> {
+    let mut records: Vec<&SwfRecord> = records.iter().filter(|x| filter.accepts(x)).collect();
+    records.sort_by(|a, b| a.submit_time.total_cmp(&b.submit_time));
+    let Some(first) = records.first() else {
+        return Ok(vec![]);
+    };
+    let start = first.submit_time.max(0.0);
+    let mut queue: Vec<ProcessSpec> = records
+        .iter()
+        .map(|record| {
+            let mut process = create(
+                &format!("job_{}", record.job_number),
+                (record.submit_time.max(0.0) - start) as f32,
+                record.run_time.max(0.0) as f32,
+                None,
+            );
+            process.job = Some(JobInfo {
+                job_number: record.job_number.max(0) as u64,
+                requested_processors: record.processors(),
+                requested_time: (record.requested_time >= 0.0)
+                    .then_some(record.requested_time as f32),
+                user_id: known(record.user_id),
+                group_id: known(record.group_id),
+            });
+            process
+        })
+        .collect();
+    fix_processes(&mut queue)?;
+    Ok(queue)
+}
+
+/// Writes a queue as an SWF log. The format only holds whole seconds,
+/// so times are rounded. Jobs are marked as completed,
+/// and fields a queue doesn't have are set to -1.
+pub fn processes_to_swf(queue: &[ProcessSpec]) -> String {
+    let mut output =
+        "; Version: 2.2\n; Note: Generated queue, times are rounded to seconds\n".to_owned();
+    for (i, process) in queue.iter().enumerate() {
+        let job = process.job.as_ref();
+        let processors = job
+            .and_then(|x| x.requested_processors)
+            .map_or(1, |x| x as i64);
+        let optional = |field: Option<u32>| field.map_or(-1, |x| x as i64);
+        let fields = [
+            job.map_or(i as i64 + 1, |x| x.job_number as i64),
+            process.arrival_time.round() as i64,
+            -1,
+            process.burst_time.round() as i64,
+            processors,
+            -1,
+            -1,
+            processors,
+            job.and_then(|x| x.requested_time)
+                .map_or(-1, |x| x.round() as i64),
+            -1,
+            1,
+            optional(job.and_then(|x| x.user_id)),
+            optional(job.and_then(|x| x.group_id)),
+            -1,
+            -1,
+            -1,
+            -1,
+            -1,
+        ];
+        let fields: Vec<String> = fields.iter().map(|x| x.to_string()).collect();
+        output += &fields.join(" ");
+        output += "\n";
+    }
+    output
+}
+
+pub fn save_swf_to_path(queue: &[ProcessSpec], path: &str) -> Result<(), Error> {
+    fs::write(path, processes_to_swf(queue)).map_err(|error| Error::from(error).in_file(path))
+}
+
+fn parse_swf_line(line: &str) -> Option<SwfRecord> {
+    let fields: Vec<f64> = line
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    if fields.len() != 18 {
+        return None;
+    }
+    Some(SwfRecord {
+        job_number: fields[0] as i64,
+        submit_time: fields[1],
+        wait_time: fields[2],
+        run_time: fields[3],
+        allocated_processors: fields[4] as i64,
+        average_cpu_time: fields[5],
+        used_memory: fields[6],
+        requested_processors: fields[7] as i64,
+        requested_time: fields[8],
+        requested_memory: fields[9],
+        status: fields[10] as i64,
+        user_id: fields[11] as i64,
+        group_id: fields[12] as i64,
+        executable_number: fields[13] as i64,
+        queue_number: fields[14] as i64,
+        partition_number: fields[15] as i64,
+        preceding_job_number: fields[16] as i64,
+        think_time: fields[17],
+    })
+}
+
+/// SWF marks missing values with -1
+fn known(field: i64) -> Option<u32> {
+    u32::try_from(field).ok()
+}
+
+#[cfg(test)]
+mod swf_tests {
+    use super::*;
+
+    fn get_records() -> Vec<SwfRecord> {
+        read_swf_from("test_data/swf_sample.swf").unwrap()
+    }
+
+    #[test]
+    fn test_read_swf() {
+        let records = get_records();
+        assert!(records.len() == 10);
+        let first = records[0];
+        assert!(first.job_number == 1 && first.submit_time == 0.0 && first.run_time == 1800.0);
+        assert!(first.requested_processors == 16 && first.user_id == 3 && first.group_id == 1);
+
+        let error = read_swf("1 0 10\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, Error::Parse { line: Some(1), .. }));
+        assert!(error.to_string().starts_with("line 1:"));
+        let error = read_swf_from("test_data/missing.swf").unwrap_err();
+        assert!(error.to_string().starts_with("test_data/missing.swf: "));
+    }
+
+    #[test]
+    fn test_filters() {
+        let records = get_records();
+        assert!(
+            swf_to_processes(&records, &SwfFilter::default())
+                .unwrap()
+                .len()
+                == 10
+        );
+        let valid = SwfFilter {
+            drop_invalid: true,
+            ..Default::default()
+        };
+        // Job 4 has no run time, job 7 no processor count
+        assert!(swf_to_processes(&records, &valid).unwrap().len() == 8);
+        let completed = SwfFilter {
+            drop_invalid: true,
+            completed_only: true,
+            ..Default::default()
+        };
+        assert!(swf_to_processes(&records, &completed).unwrap().len() == 6);
+
+        let window = SwfFilter {
+            time_window: Some((1000.0, 5000.0)),
+            drop_invalid: true,
+            ..Default::default()
+        };
+        let queue = swf_to_processes(&records, &window).unwrap();
+        assert!(queue
+            .iter()
+            .map(|x| x.name.as_str())
+            .eq(["job_3", "job_5", "job_6"]));
+        assert!(queue[0].arrival_time == 0.0);
+        let job = queue[1].job.as_ref().unwrap();
+        assert!(job.job_number == 5 && job.requested_processors == Some(4));
+
+        let duplicated = [records[0], records[0]];
+        let queue = swf_to_processes(&duplicated, &SwfFilter::default()).unwrap();
+        assert!(queue[0].name == "job_1" && queue[1].name == "job_1_2");
+    }
+
+    #[test]
+    fn test_swf_round_trip() {
+        let filter = SwfFilter {
+            drop_invalid: true,
+            ..Default::default()
+        };
+        let queue = swf_to_processes(&get_records(), &filter).unwrap();
+        let exported = processes_to_swf(&queue);
+        let records = read_swf(exported.as_bytes()).unwrap();
+        let reimported = swf_to_processes(&records, &SwfFilter::default()).unwrap();
+        assert!(reimported.len() == queue.len());
+        for (a, b) in queue.iter().zip(&reimported) {
+            assert!(a.name == b.name && a.job == b.job);
+            assert!(a.arrival_time == b.arrival_time && a.burst_time == b.burst_time);
+        }
+        let completed = SwfFilter {
+            completed_only: true,
+            ..Default::default()
+        };
+        assert!(swf_to_processes(&records, &completed).unwrap().len() == queue.len());
+    }
+
+    #[test]
+    fn test_export_generated_queue() {
+        let queue = vec![create("A", 0.4, 2.6, Some(1)), create("B", 3.0, 1.0, None)];
+        let lines: Vec<String> = processes_to_swf(&queue)
+            .lines()
+            .filter(|x| !x.starts_with(';'))
+            .map(|x| x.to_owned())
+            .collect();
+        assert!(lines[0] == "1 0 -1 3 1 -1 -1 1 -1 -1 1 -1 -1 -1 -1 -1 -1 -1");
+        assert!(lines[1].starts_with("2 3 -1 1 "));
+    }
+}
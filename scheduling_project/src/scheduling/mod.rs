@@ -1,16 +1,15 @@
-use std::{
-    fs::File,
-    io::{Error, Write},
-    vec,
-};
+use std::{fs::File, io::Write, vec};
 
-use crate::process::Process;
+use crate::{
+    error::Error,
+    process::{control_block::ProcessControlBlock, ProcessSpec},
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 /// Simulation results for scheduling simulations
 pub struct SimulationResults {
-    processes: Vec<Process>,
+    processes: Vec<ProcessSpec>,
     pub average_waiting_time: f32,
     total_execution_time: f32,
     processing_history: Vec<ProcessingRecord>,
@@ -18,7 +17,7 @@ pub struct SimulationResults {
 
 /// Utility functions making it easier to read simulation results
 impl SimulationResults {
-    pub fn print(&self) -> () {
+    pub fn print(&self) {
         println!("Processes count: {}\nAverage waiting time: {}\nTotal execution time: {}\nDetailed processing history:", self.processes.len(), self.average_waiting_time, self.total_execution_time);
         for record in &self.processing_history {
             println!(
@@ -29,6 +28,12 @@ impl SimulationResults {
     }
 
     pub fn to_csv(&self, path: &str) -> Result<(), Error> {
+        File::create(path)
+            .and_then(|mut file| file.write_all(self.to_csv_string().as_bytes()))
+            .map_err(|error| Error::from(error).in_file(path))
+    }
+
+    pub fn to_csv_string(&self) -> String {
         let mut output: String = "Time,Process_name,Processing_time".to_owned();
         for record in &self.processing_history {
             output = format!(
@@ -36,9 +41,7 @@ impl SimulationResults {
                 output, record.start_time, record.process_name, record.duration
             )
         }
-        let mut file = File::create(path).unwrap();
-        file.write_all(output.as_bytes()).unwrap();
-        Ok(())
+        output
     }
 }
 
@@ -51,21 +54,28 @@ pub struct ProcessingRecord {
     pub process_name: String,
 }
 
-/// FCFS scheduling algorithm implementation
-pub fn fcfs(queue: Vec<Process>) -> SimulationResults {
+/// FCFS scheduling algorithm implementation.
+/// Expects the queue sorted by arrival time, see `validation::fix_processes`
+pub fn fcfs(queue: Vec<ProcessSpec>) -> SimulationResults {
     let mut time: f32 = 0.0;
     let mut history: Vec<ProcessingRecord> = vec![];
     let mut total_waiting_time: f32 = 0.0;
-    for process in &queue {
+    for mut process in queue.iter().map(ProcessControlBlock::new) {
+        // The CPU stays idle until the process arrives
+        time = time.max(process.spec.arrival_time);
+        process.admit();
+        process.dispatch(time);
         history.push(ProcessingRecord {
             start_time: time,
-            duration: process.burst_time,
-            process_name: process.name.clone(),
+            duration: process.remaining_time(),
+            process_name: process.spec.name.clone(),
         });
-        total_waiting_time += time - process.arrival_time;
-        time += process.burst_time;
+        time += process.remaining_time();
+        process.terminate(time);
+        total_waiting_time += process.waiting_time();
     }
-    let processes_count = queue.len() as f32;
+    // An empty queue has nobody waiting
+    let processes_count = queue.len().max(1) as f32;
     SimulationResults {
         processes: queue,
         average_waiting_time: total_waiting_time / processes_count,
@@ -83,7 +93,7 @@ mod fcfs_convoy_effect_tests {
     use super::*;
     #[test]
     fn convoy_effect_bad() {
-        let queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P1", 0.0, 24.0, None),
             process::create("P2", 0.0, 3.0, None),
             process::create("P3", 0.0, 3.0, None),
@@ -94,7 +104,7 @@ mod fcfs_convoy_effect_tests {
 
     #[test]
     fn convoy_effect_good() {
-        let queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P1", 0.0, 3.0, None),
             process::create("P2", 0.0, 3.0, None),
             process::create("P3", 0.0, 24.0, None),
@@ -102,60 +112,75 @@ mod fcfs_convoy_effect_tests {
         let result = fcfs(queue);
         assert!(result.average_waiting_time == 3.0);
     }
+
+    #[test]
+    fn idle_until_arrival() {
+        let queue: Vec<ProcessSpec> = vec![
+            process::create("P1", 0.0, 3.0, None),
+            process::create("P2", 10.0, 3.0, None),
+        ];
+        let result = fcfs(queue);
+        assert!(result.average_waiting_time == 0.0);
+        assert!(result.total_execution_time == 13.0);
+    }
 }
 
-/// Round-robin algorithm implementation
-pub fn round_robin(mut queue: Vec<Process>, processing_time: f32) -> SimulationResults {
-    let original_queue = queue.clone();
-    let mut time: f32 = 0.0;
+/// Round-robin algorithm implementation.
+/// Expects the queue sorted by arrival time, see `validation::fix_processes`.
+/// Fails unless the time quantum is finite and positive.
+pub fn round_robin(
+    queue: Vec<ProcessSpec>,
+    processing_time: f32,
+) -> Result<SimulationResults, Error> {
+    if !(processing_time.is_finite() && processing_time > 0.0) {
+        return Err(Error::configuration(format!(
+            "round-robin time quantum must be positive, got {}",
+            processing_time
+        )));
+    }
     let mut history: Vec<ProcessingRecord> = vec![];
-    let mut arrived_processes: Vec<Process> = vec![];
     let mut current_process_index = 0;
     let mut all_processes_total_waiting_time: f32 = 0.0;
-    let queue_size = queue.len() as f32;
+    let queue_size = queue.len().max(1) as f32;
 
-    time = queue[0].arrival_time;
-    simulate_processes_arrival(
-        &mut queue, // a mutable reference allows to modify the original object
-        &mut arrived_processes,
-        &mut time,
-    );
+    let Some(first_process) = queue.first() else {
+        return Ok(SimulationResults {
+            processes: queue,
+            average_waiting_time: 0.0,
+            total_execution_time: 0.0,
+            processing_history: history,
+        });
+    };
+    let mut time: f32 = first_process.arrival_time;
+    let mut new_processes: Vec<ProcessControlBlock> =
+        queue.iter().map(ProcessControlBlock::new).collect();
+    let mut arrived_processes: Vec<ProcessControlBlock> = vec![];
+    simulate_processes_arrival(&mut new_processes, &mut arrived_processes, &mut time);
 
-    while arrived_processes.len() > 0 || queue.len() > 0 {
+    while !arrived_processes.is_empty() || !new_processes.is_empty() {
         let current_process = &mut arrived_processes[current_process_index];
+        current_process.dispatch(time);
 
-        // Log waiting time
-        if current_process.waiting_since.is_some() && current_process.total_waiting_time.is_some() {
-            let waiting_time = time - current_process.waiting_since.unwrap();
-            let total_waiting_time = current_process.total_waiting_time.unwrap();
-            current_process.total_waiting_time = Some(total_waiting_time + waiting_time);
-        } else {
-            current_process.total_waiting_time = Some(time - current_process.arrival_time);
-        }
-
-        let mut processed_for: f32 = 0.0;
-        if current_process.burst_time > processing_time {
+        let processed_for: f32;
+        if current_process.remaining_time() > processing_time {
             // Process and expropriate
             processed_for = processing_time;
             time += processing_time;
-            current_process.burst_time -= processing_time;
-            current_process.waiting_since = Some(time);
-            save_processing_record(&mut history, &time, &processed_for, current_process);
+            current_process.preempt(time, processed_for);
+            save_processing_record(&mut history, &time, &processed_for, current_process.spec);
         } else {
             // Process and finish
-            processed_for = current_process.burst_time;
-            time += current_process.burst_time;
-            current_process.burst_time = 0.0;
-            all_processes_total_waiting_time += current_process.total_waiting_time.unwrap();
-            save_processing_record(&mut history, &time, &processed_for, current_process);
+            processed_for = current_process.remaining_time();
+            time += processed_for;
+            current_process.terminate(time);
+            all_processes_total_waiting_time += current_process.waiting_time();
+            save_processing_record(&mut history, &time, &processed_for, current_process.spec);
             arrived_processes.remove(current_process_index);
-            if current_process_index != 0 {
-                current_process_index -= 1;
-            }
+            current_process_index = current_process_index.saturating_sub(1);
         }
 
-        if queue.len() > 0 {
-            simulate_processes_arrival(&mut queue, &mut arrived_processes, &mut time);
+        if !new_processes.is_empty() {
+            simulate_processes_arrival(&mut new_processes, &mut arrived_processes, &mut time);
         }
 
         // Determine the next process
@@ -166,34 +191,35 @@ pub fn round_robin(mut queue: Vec<Process>, processing_time: f32) -> SimulationR
         }
     }
     let average_waiting_time = all_processes_total_waiting_time / queue_size;
-    SimulationResults {
-        processes: original_queue,
+    Ok(SimulationResults {
+        processes: queue,
         average_waiting_time,
         total_execution_time: time,
         processing_history: history,
-    }
+    })
 }
 
-fn simulate_processes_arrival(
-    queue: &mut Vec<Process>,
-    arrived_processes: &mut Vec<Process>,
+/// Admits the processes that arrived by `time`, moving on
+/// to the next arrival if none is ready to run
+fn simulate_processes_arrival<'a>(
+    new_processes: &mut Vec<ProcessControlBlock<'a>>,
+    arrived_processes: &mut Vec<ProcessControlBlock<'a>>,
     time: &mut f32,
 ) {
-    let mut indices_to_be_removed: Vec<usize> = vec![];
-    for i in 0..queue.len() {
-        let next_process = &queue[i];
-        if next_process.arrival_time <= *time {
-            arrived_processes.push(queue[i].clone());
-            indices_to_be_removed.push(i);
+    let mut i = 0;
+    while i < new_processes.len() {
+        if new_processes[i].spec.arrival_time <= *time {
+            let mut process = new_processes.remove(i);
+            process.admit();
+            arrived_processes.push(process);
+        } else {
+            i += 1;
         }
     }
-    for i in (0..indices_to_be_removed.len()).rev() {
-        queue.remove(indices_to_be_removed[i]);
-    }
-    if arrived_processes.len() == 0 {
-        let next_arrival_time = queue[0].arrival_time;
+    if arrived_processes.is_empty() {
+        let next_arrival_time = new_processes[0].spec.arrival_time;
         *time = next_arrival_time;
-        simulate_processes_arrival(queue, arrived_processes, time);
+        simulate_processes_arrival(new_processes, arrived_processes, time);
     }
 }
 
@@ -201,7 +227,7 @@ fn save_processing_record(
     history: &mut Vec<ProcessingRecord>,
     time: &f32,
     processed_for: &f32,
-    current_process: &mut Process,
+    current_process: &ProcessSpec,
 ) {
     history.push(ProcessingRecord {
         start_time: *time,
@@ -212,18 +238,18 @@ fn save_processing_record(
 
 #[cfg(test)]
 mod round_robin_tests {
-    use crate::process;
+    use crate::process::{self, control_block::ProcessState};
 
     use super::*;
     #[test]
     /// Basic test with data from a lecture
     fn test_round_robin() {
-        let queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P1", 0.0, 24.0, None),
             process::create("P2", 0.0, 3.0, None),
             process::create("P3", 0.0, 3.0, None),
         ];
-        let result = round_robin(queue, 4.0);
+        let result = round_robin(queue, 4.0).unwrap();
         let rounded_waiting_time = (result.average_waiting_time * 100.0).round() / 100.0;
         assert!(result.total_execution_time == 30.0);
         assert!(rounded_waiting_time == 5.67); // Floating point math in action. This should be just 5.67
@@ -232,12 +258,12 @@ mod round_robin_tests {
     #[test]
     /// Testing differing arrival times
     fn test_sequential_arrival_times() {
-        let queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P1", 1.0, 4.0, None),
             process::create("P2", 5.0, 4.0, None),
             process::create("P3", 9.0, 4.0, None),
         ];
-        let result = round_robin(queue, 4.0);
+        let result = round_robin(queue, 4.0).unwrap();
         let rounded_waiting_time = (result.average_waiting_time * 100.0).round() / 100.0;
         assert!(rounded_waiting_time == 0.0);
         assert!(result.total_execution_time == 13.0);
@@ -246,26 +272,57 @@ mod round_robin_tests {
     #[test]
     /// Testing what happens when there's a gap between one process and another
     fn test_arrival_with_gaps_times() {
-        let queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P1", 1.0, 4.0, None),
             process::create("P2", 5.0, 4.0, None),
             process::create("P3", 9.0, 4.0, None),
         ];
-        let result = round_robin(queue, 4.0);
+        let result = round_robin(queue, 4.0).unwrap();
         let rounded_waiting_time = (result.average_waiting_time * 100.0).round() / 100.0;
         assert!(rounded_waiting_time == 0.0);
     }
 
+    #[test]
+    fn test_empty_queue() {
+        let result = round_robin(vec![], 2.0).unwrap();
+        assert!(result.average_waiting_time == 0.0);
+        assert!(result.total_execution_time == 0.0);
+        assert!(fcfs(vec![]).average_waiting_time == 0.0);
+    }
+
+    #[test]
+    fn test_invalid_quantum() {
+        for quantum in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let queue = vec![process::create("P1", 0.0, 4.0, None)];
+            let error = round_robin(queue, quantum).err().unwrap();
+            assert!(matches!(error, Error::Configuration(_)));
+        }
+    }
+
     #[test]
     fn test_process_arrival_simulation() {
-        let mut queue: Vec<Process> = vec![
+        let queue: Vec<ProcessSpec> = vec![
             process::create("P2", 5.0, 4.0, None),
             process::create("P3", 9.0, 4.0, None),
         ];
-        let mut arrived_processes: Vec<Process> = vec![];
+        let mut new_processes: Vec<ProcessControlBlock> =
+            queue.iter().map(ProcessControlBlock::new).collect();
+        let mut arrived_processes: Vec<ProcessControlBlock> = vec![];
         let mut time = 5.0;
-        simulate_processes_arrival(&mut queue, &mut arrived_processes, &mut time);
-        assert!(queue.len() == 1);
+        simulate_processes_arrival(&mut new_processes, &mut arrived_processes, &mut time);
+        assert!(new_processes.len() == 1);
         assert!(arrived_processes.len() == 1);
+        assert!(arrived_processes[0].state() == ProcessState::Ready);
+    }
+
+    #[test]
+    fn test_queue_left_untouched() {
+        let queue: Vec<ProcessSpec> = vec![
+            process::create("P1", 0.0, 5.0, None),
+            process::create("P2", 0.0, 3.0, None),
+        ];
+        let result = round_robin(queue, 2.0).unwrap();
+        assert!(result.processes[0].burst_time == 5.0);
+        assert!(result.processes[1].burst_time == 3.0);
     }
 }
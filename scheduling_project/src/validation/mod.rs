@@ -0,0 +1,242 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{error::Error, process::ProcessSpec};
+
+/// Something the simulations would silently get wrong
+#[derive(Clone, PartialEq, Debug)]
+pub enum Problem {
+    /// The process arrives before the one preceding it in the queue,
+    /// which `fcfs` and `round_robin` don't expect
+    NotSortedByArrival { name: String, index: usize },
+    /// Negative, infinite or NaN arrival time
+    InvalidArrivalTime { name: String, arrival_time: f32 },
+    /// Negative, infinite or NaN burst time
+    InvalidBurstTime { name: String, burst_time: f32 },
+    /// Several processes share a name, so they can't be told apart in the history
+    DuplicateName { name: String, count: usize },
+    /// A reference to an address the reference string isn't supposed to use
+    ReferenceOutOfRange {
+        index: usize,
+        page: u64,
+        addresses_count: u64,
+    },
+}
+
+impl Problem {
+    /// Whether `fix_processes` can fix the problem without changing what gets simulated
+    pub fn is_fixable(&self) -> bool {
+        matches!(
+            self,
+            Problem::NotSortedByArrival { .. } | Problem::DuplicateName { .. }
+        )
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::NotSortedByArrival { name, index } => write!(
+                f,
+                "process {} (#{}) arrives before the previous one",
+                name, index
+            ),
+            Problem::InvalidArrivalTime { name, arrival_time } => {
+                write!(f, "process {} arrives at {}", name, arrival_time)
+            }
+            Problem::InvalidBurstTime { name, burst_time } => {
+                write!(f, "process {} has a burst time of {}", name, burst_time)
+            }
+            Problem::DuplicateName { name, count } => {
+                write!(f, "{} processes are named {}", count, name)
+            }
+            Problem::ReferenceOutOfRange {
+                index,
+                page,
+                addresses_count,
+            } => write!(
+                f,
+                "reference #{} to page {} is outside of the {} addresses",
+                index, page, addresses_count
+            ),
+        }
+    }
+}
+
+/// Lists every problem of a queue
+pub fn check_processes(queue: &[ProcessSpec]) -> Vec<Problem> {
+    let mut problems = vec![];
+    for (i, process) in queue.iter().enumerate() {
+        if !(process.arrival_time.is_finite() && process.arrival_time >= 0.0) {
+            problems.push(Problem::InvalidArrivalTime {
+                name: process.name.clone(),
+                arrival_time: process.arrival_time,
+            });
+        }
+        if !(process.burst_time.is_finite() && process.burst_time >= 0.0) {
+            problems.push(Problem::InvalidBurstTime {
+                name: process.name.clone(),
+                burst_time: process.burst_time,
+            });
+        }
+        if i > 0 && process.arrival_time < queue[i - 1].arrival_time {
+            problems.push(Problem::NotSortedByArrival {
+                name: process.name.clone(),
+                index: i,
+            });
+        }
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for process in queue {
+        *counts.entry(&process.name).or_insert(0) += 1;
+    }
+    // In queue order, so that reports are stable
+    for process in queue {
+        if let Some(count) = counts.remove(process.name.as_str()).filter(|x| *x > 1) {
+            problems.push(Problem::DuplicateName {
+                name: process.name.clone(),
+                count,
+            });
+        }
+    }
+    problems
+}
+
+/// Fails with every problem of the queue at once
+pub fn validate_processes(queue: &[ProcessSpec]) -> Result<(), Error> {
+    into_result(check_processes(queue))
+}
+
+/// Sorts the queue by arrival time (keeping the order of simultaneous arrivals)
+/// and renames duplicates to `<name>_2`, `<name>_3`, etc., then checks it again.
+/// Returns the problems that were fixed, fails if some can't be fixed.
+pub fn fix_processes(queue: &mut [ProcessSpec]) -> Result<Vec<Problem>, Error> {
+    let problems = check_processes(queue);
+    // The queue is left untouched if it can't be fixed entirely
+    validate_unfixable(&problems)?;
+    queue.sort_by(|a, b| a.arrival_time.total_cmp(&b.arrival_time));
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut taken: Vec<String> = queue.iter().map(|x| x.name.clone()).collect();
+    for process in queue.iter_mut() {
+        let occurrences = seen.entry(process.name.clone()).or_insert(0);
+        *occurrences += 1;
+        if *occurrences == 1 {
+            continue;
+        }
+        let mut suffix = *occurrences;
+        let mut name = format!("{}_{}", process.name, suffix);
+        while taken.contains(&name) {
+            suffix += 1;
+            name = format!("{}_{}", process.name, suffix);
+        }
+        taken.push(name.clone());
+        process.name = name;
+    }
+    validate_processes(queue)?;
+    Ok(problems)
+}
+
+/// Lists every reference outside of [0, addresses_count)
+pub fn check_references(references: &[u64], addresses_count: u64) -> Vec<Problem> {
+    references
+        .iter()
+        .enumerate()
+        .filter(|(_, page)| **page >= addresses_count)
+        .map(|(index, page)| Problem::ReferenceOutOfRange {
+            index,
+            page: *page,
+            addresses_count,
+        })
+        .collect()
+}
+
+pub fn validate_references(references: &[u64], addresses_count: u64) -> Result<(), Error> {
+    into_result(check_references(references, addresses_count))
+}
+
+fn validate_unfixable(problems: &[Problem]) -> Result<(), Error> {
+    into_result(
+        problems
+            .iter()
+            .filter(|x| !x.is_fixable())
+            .cloned()
+            .collect(),
+    )
+}
+
+fn into_result(problems: Vec<Problem>) -> Result<(), Error> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let problems: Vec<String> = problems.iter().map(|x| x.to_string()).collect();
+    Err(Error::validation(format!(
+        "{} problem(s): {}",
+        problems.len(),
+        problems.join("; ")
+    )))
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use super::*;
+    use crate::process::create;
+
+    #[test]
+    fn test_all_problems_reported() {
+        let queue = vec![
+            create("A", 2.0, 1.0, None),
+            create("B", 1.0, f32::NAN, None),
+            create("A", 3.0, -1.0, None),
+            create("C", -1.0, 1.0, None),
+        ];
+        let problems = check_processes(&queue);
+        assert!(problems.len() == 6);
+        assert!(problems.contains(&Problem::DuplicateName {
+            name: "A".to_owned(),
+            count: 2
+        }));
+        assert!(problems.contains(&Problem::NotSortedByArrival {
+            name: "B".to_owned(),
+            index: 1
+        }));
+        let error = validate_processes(&queue).unwrap_err();
+        assert!(error.to_string().contains("6 problem(s)"));
+        assert!(validate_processes(&[create("A", 0.0, 1.0, None)]).is_ok());
+    }
+
+    #[test]
+    fn test_fix_processes() {
+        let mut queue = vec![
+            create("A", 2.0, 1.0, None),
+            create("A", 1.0, 1.0, None),
+            create("A_2", 1.0, 1.0, None),
+            create("B", 1.0, 1.0, None),
+        ];
+        let fixed = fix_processes(&mut queue).unwrap();
+        assert!(fixed.len() == 2 && fixed.iter().all(|x| x.is_fixable()));
+        let names: Vec<&str> = queue.iter().map(|x| x.name.as_str()).collect();
+        assert!(names == vec!["A", "A_2", "B", "A_3"]);
+        assert!(check_processes(&queue).is_empty());
+
+        let mut queue = vec![create("A", 1.0, -1.0, None), create("B", 0.0, 1.0, None)];
+        assert!(fix_processes(&mut queue).is_err());
+        // Nothing changes if the queue can't be fixed
+        assert!(queue[0].name == "A");
+    }
+
+    #[test]
+    fn test_check_references() {
+        let problems = check_references(&[0, 3, 4, 1, 7], 4);
+        assert!(problems.len() == 2);
+        assert!(
+            problems[0]
+                == Problem::ReferenceOutOfRange {
+                    index: 2,
+                    page: 4,
+                    addresses_count: 4
+                }
+        );
+        assert!(validate_references(&[0, 1, 2, 3], 4).is_ok());
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    error::Error,
+    paging::{PagingSimulator, ReplacementPolicy},
+    process::{
+        control_block::{ProcessControlBlock, ProcessState},
+        ProcessSpec,
+    },
+    scheduling::ProcessingRecord,
+};
+
+/// Parameters of a combined CPU scheduling and virtual memory simulation
+pub struct VirtualMemoryConfig {
+    /// Frames shared by all processes (global replacement)
+    pub physical_memory_size: usize,
+    /// Round-robin time quantum
+    pub processing_time: f32,
+    /// Time needed by the paging device to service a single page fault.
+    /// Faults are serviced one at a time.
+    pub page_fault_latency: f32,
+}
+
+/// Results of a combined CPU scheduling and virtual memory simulation
+pub struct VirtualMemorySimulationResults {
+    pub processes_count: usize,
+    pub total_execution_time: f32,
+    pub cpu_busy_time: f32,
+    pub cpu_utilization: f32,
+    pub references_count: usize,
+    pub page_faults: usize,
+    pub average_turnaround_time: f32,
+    pub processing_history: Vec<ProcessingRecord>,
+}
+
+/// Round-robin scheduling of processes sharing physical memory.
+/// Every process makes its `page_references` evenly spread across its burst
+/// time. A page fault blocks the process until the paging device loads
+/// the page, while the CPU runs other ready processes. Once unblocked,
+/// the process repeats the reference, which faults again if the page
+/// was evicted in the meantime. Processes without page references never fault.
+pub fn simulate_virtual_memory<P: ReplacementPolicy>(
+    queue: Vec<ProcessSpec>,
+    config: VirtualMemoryConfig,
+    policy: P,
+) -> Result<VirtualMemorySimulationResults, Error> {
+    let mut simulator = PagingSimulator::new(config.physical_memory_size, policy)?;
+    // Every (process, page) pair gets its own identifier in the shared frame table
+    let mut global_pages: HashMap<(usize, u64), u64> = HashMap::new();
+    let references: Vec<Vec<u64>> = queue
+        .iter()
+        .map(|process| process.page_references.clone().unwrap_or_default())
+        .collect();
+    let mut next_reference: Vec<usize> = vec![0; queue.len()];
+    let mut processes: Vec<ProcessControlBlock> =
+        queue.iter().map(ProcessControlBlock::new).collect();
+    // When the page waiting processes faulted on gets loaded
+    let mut blocked_until: Vec<Option<f32>> = vec![None; queue.len()];
+    let mut ready: VecDeque<usize> = VecDeque::new();
+    let mut history: Vec<ProcessingRecord> = vec![];
+    let mut time: f32 = 0.0;
+    let mut cpu_busy_time: f32 = 0.0;
+    let mut disk_free_at: f32 = 0.0;
+    let mut page_faults = 0;
+
+    loop {
+        admit_processes(&mut processes, time, &mut blocked_until, &mut ready);
+        let Some(current) = ready.pop_front() else {
+            // Nothing to run, the CPU stays idle until something arrives or gets unblocked
+            let next_arrival = processes
+                .iter()
+                .filter(|x| x.state() == ProcessState::New)
+                .map(|x| x.spec.arrival_time);
+            let next_unblock = blocked_until.iter().flatten().copied();
+            match next_arrival
+                .chain(next_unblock)
+                .min_by(|a, b| a.total_cmp(b))
+            {
+                Some(next_event) => time = next_event.max(time),
+                None => break,
+            }
+            continue;
+        };
+
+        let start_time = time;
+        processes[current].dispatch(time);
+        let mut remaining_burst = processes[current].remaining_time();
+        let reference_time = match references[current].len() {
+            0 => remaining_burst,
+            count => queue[current].burst_time / count as f32,
+        };
+        while time - start_time < config.processing_time && remaining_burst > 0.0 {
+            if let Some(page) = references[current].get(next_reference[current]) {
+                let next_global_page = global_pages.len() as u64;
+                let global_page = *global_pages
+                    .entry((current, *page))
+                    .or_insert(next_global_page);
+                if !simulator.reference(global_page) {
+                    page_faults += 1;
+                    disk_free_at = disk_free_at.max(time) + config.page_fault_latency;
+                    blocked_until[current] = Some(disk_free_at);
+                    break;
+                }
+                next_reference[current] += 1;
+            }
+            let processed_for = reference_time
+                .min(remaining_burst)
+                .min(config.processing_time - (time - start_time));
+            time += processed_for;
+            remaining_burst -= processed_for;
+            if next_reference[current] == references[current].len()
+                && !references[current].is_empty()
+            {
+                remaining_burst = 0.0;
+            }
+        }
+
+        if time > start_time {
+            cpu_busy_time += time - start_time;
+            history.push(ProcessingRecord {
+                start_time,
+                duration: time - start_time,
+                process_name: queue[current].name.clone(),
+            });
+        }
+        let processed_for = processes[current].remaining_time() - remaining_burst;
+        if remaining_burst <= 0.0 {
+            processes[current].terminate(time);
+        } else if blocked_until[current].is_some() {
+            processes[current].block(processed_for);
+        } else {
+            processes[current].preempt(time, processed_for);
+            // Processes that arrived in the meantime go first
+            admit_processes(&mut processes, time, &mut blocked_until, &mut ready);
+            ready.push_back(current);
+        }
+    }
+
+    let total_turnaround_time: f32 = processes
+        .iter()
+        .map(|x| x.finish_time().unwrap_or(time) - x.spec.arrival_time)
+        .sum();
+    let first_arrival = queue
+        .iter()
+        .map(|x| x.arrival_time)
+        .min_by(|a, b| a.total_cmp(b))
+        .unwrap_or(0.0);
+    let total_execution_time = time - first_arrival;
+    Ok(VirtualMemorySimulationResults {
+        processes_count: queue.len(),
+        total_execution_time,
+        cpu_busy_time,
+        cpu_utilization: cpu_busy_time / total_execution_time.max(f32::EPSILON),
+        references_count: references.iter().map(|x| x.len()).sum(),
+        page_faults,
+        average_turnaround_time: total_turnaround_time / queue.len().max(1) as f32,
+        processing_history: history,
+    })
+}
+
+/// Runs the simulation with the first 1, 2, ..., n processes of the queue,
+/// so that CPU utilization can be plotted against the degree of multiprogramming
+pub fn sweep_multiprogramming_degree<P, F>(
+    queue: &[ProcessSpec],
+    config: VirtualMemoryConfig,
+    policy: F,
+) -> Result<Vec<VirtualMemorySimulationResults>, Error>
+where
+    P: ReplacementPolicy,
+    F: Fn() -> P,
+{
+    (1..=queue.len())
+        .map(|degree| {
+            let config = VirtualMemoryConfig {
+                physical_memory_size: config.physical_memory_size,
+                processing_time: config.processing_time,
+                page_fault_latency: config.page_fault_latency,
+            };
+            simulate_virtual_memory(queue[..degree].to_vec(), config, policy())
+        })
+        .collect()
+}
+
+/// Moves processes that arrived or got their page loaded by `time`
+/// to the ready queue, in the order it happened
+fn admit_processes(
+    processes: &mut [ProcessControlBlock],
+    time: f32,
+    blocked_until: &mut [Option<f32>],
+    ready: &mut VecDeque<usize>,
+) {
+    let mut admitted: Vec<(f32, usize)> = vec![];
+    for (i, process) in processes.iter_mut().enumerate() {
+        if process.state() == ProcessState::New && process.spec.arrival_time <= time {
+            process.admit();
+            admitted.push((process.spec.arrival_time, i));
+        }
+    }
+    for (i, unblock_time) in blocked_until.iter_mut().enumerate() {
+        if let Some(unblocked_at) = unblock_time.filter(|x| *x <= time) {
+            processes[i].wake(unblocked_at);
+            admitted.push((unblocked_at, i));
+            *unblock_time = None;
+        }
+    }
+    admitted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    ready.extend(admitted.into_iter().map(|(_, i)| i));
+}
+
+#[cfg(test)]
+mod virtual_memory_tests {
+    use super::*;
+    use crate::{paging::LeastRecentlyUsed, process};
+
+    fn get_queue(processes_count: usize) -> Vec<ProcessSpec> {
+        (0..processes_count)
+            .map(|i| {
+                let mut process = process::create(&format!("P{}", i), 0.0, 400.0, None);
+                process.page_references = Some((0..400).map(|x| x % 8).collect());
+                process
+            })
+            .collect()
+    }
+
+    fn get_config() -> VirtualMemoryConfig {
+        VirtualMemoryConfig {
+            physical_memory_size: 16,
+            processing_time: 10.0,
+            page_fault_latency: 50.0,
+        }
+    }
+
+    #[test]
+    fn test_single_process_cold_faults() {
+        let results =
+            simulate_virtual_memory(get_queue(1), get_config(), LeastRecentlyUsed::default())
+                .unwrap();
+        assert!(results.page_faults == 8);
+        assert!(results.cpu_busy_time == 400.0);
+        assert!(results.total_execution_time == 800.0);
+    }
+
+    #[test]
+    fn test_process_without_references() {
+        let queue = vec![process::create("P1", 2.0, 30.0, None)];
+        let results =
+            simulate_virtual_memory(queue, get_config(), LeastRecentlyUsed::default()).unwrap();
+        assert!(results.page_faults == 0);
+        assert!(results.processing_history.len() == 3);
+        assert!(results.cpu_utilization == 1.0);
+    }
+
+    #[test]
+    fn test_thrashing() {
+        let results =
+            sweep_multiprogramming_degree(&get_queue(6), get_config(), LeastRecentlyUsed::default)
+                .unwrap();
+        // Working sets of two processes fit into memory, six don't
+        assert!(results[1].cpu_utilization > results[0].cpu_utilization);
+        assert!(results[5].cpu_utilization < results[1].cpu_utilization / 2.0);
+    }
+}
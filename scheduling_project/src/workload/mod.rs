@@ -0,0 +1,611 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::Error,
+    paging::generators::{
+        generate_references, PhasedWorkingSet, ReferenceGenerator, Scan, ZipfPopularity,
+    },
+    process::{
+        arrivals::{ArrivalProcess, ArrivalTrace, OnOffBursts, Periodic, Poisson, UniformSteps},
+        bursts::BurstDistribution,
+        read_json_from, IoRequest, ProcessSpec,
+    },
+    validation::{validate_processes, validate_references},
+};
+
+/// Spec format version understood by this crate
+pub const WORKLOAD_SPEC_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// Declarative description of a workload made of named sub-populations,
+/// read from JSON or TOML files
+pub struct WorkloadSpec {
+    pub version: u32,
+    /// Seed of every random choice made while generating the workload
+    pub seed: u64,
+    #[serde(default)]
+    pub populations: Vec<PopulationSpec>,
+    /// Other spec files, relative to this one, whose populations are added to this spec.
+    /// Their seeds are ignored, the whole workload is generated from this spec's seed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// A class of similar processes
+pub struct PopulationSpec {
+    /// Also used as the prefix of process names, has to be unique within a workload
+    pub name: String,
+    pub processes_count: usize,
+    pub arrivals: ArrivalSpec,
+    pub bursts: BurstDistribution,
+    /// Inclusive range priorities are drawn from, processes have no priority if missing
+    #[serde(default)]
+    pub priorities: Option<(u16, u16)>,
+    /// Memory access pattern, only used by virtual memory simulations
+    #[serde(default)]
+    pub page_references: Option<PageReferencesSpec>,
+    /// Blocking I/O made by every process, processes do no I/O if missing
+    #[serde(default)]
+    pub io_pattern: Option<IoPatternSpec>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ArrivalSpec {
+    UniformSteps {
+        step_probability: f64,
+    },
+    Poisson {
+        rate: f32,
+    },
+    OnOffBursts {
+        on_rate: f32,
+        off_rate: f32,
+        mean_on_duration: f32,
+        mean_off_duration: f32,
+    },
+    Periodic {
+        period: f32,
+        jitter: f32,
+    },
+    /// Arrival times replayed from a JSON file, relative to the spec
+    Trace {
+        path: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+// serde can't deny unknown fields next to a flattened one, the pattern
+// is read from the remaining fields by `TryFrom` instead
+#[serde(try_from = "Map<String, Value>")]
+pub struct PageReferencesSpec {
+    pub references_count: usize,
+    #[serde(flatten)]
+    pub pattern: ReferencePatternSpec,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "pattern", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ReferencePatternSpec {
+    PhasedWorkingSet {
+        pages_count: u64,
+        working_set_size: u64,
+        phase_length: usize,
+    },
+    Zipf {
+        pages_count: u64,
+        exponent: f64,
+    },
+    SequentialScan {
+        start: u64,
+        length: u64,
+    },
+    LoopingScan {
+        start: u64,
+        length: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// Blocking I/O requests spread over the burst of a process
+pub struct IoPatternSpec {
+    /// CPU time before the first request and between consecutive ones
+    pub interval: BurstDistribution,
+    /// Time every request takes to complete
+    pub duration: BurstDistribution,
+}
+
+/// Reads a spec along with every spec it includes.
+/// Files ending with `.toml` are read as TOML, anything else as JSON.
+/// Fails if a file is included more than once, e.g. by itself.
+pub fn read_workload_spec_from(path: &str) -> Result<WorkloadSpec, Error> {
+    read_workload_spec(path, &mut HashSet::new())
+}
+
+fn read_workload_spec(path: &str, visited: &mut HashSet<PathBuf>) -> Result<WorkloadSpec, Error> {
+    let canonical = fs::canonicalize(path).map_err(|error| Error::from(error).in_file(path))?;
+    if !visited.insert(canonical) {
+        return Err(Error::configuration(format!(
+            "{}: included more than once",
+            path
+        )));
+    }
+    let mut spec: WorkloadSpec = match path.ends_with(".toml") {
+        true => read_toml_from(path)?,
+        false => read_json_from(path)?,
+    };
+    check_version(&spec).map_err(|error| prefix_path(error, path))?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    // Paths in the spec are relative to its file
+    for population in spec.populations.iter_mut() {
+        if let ArrivalSpec::Trace { path } = &mut population.arrivals {
+            *path = directory.join(&*path).to_string_lossy().into_owned();
+        }
+    }
+    for include in std::mem::take(&mut spec.includes) {
+        let included = read_workload_spec(&directory.join(include).to_string_lossy(), visited)?;
+        spec.populations.extend(included.populations);
+    }
+    Ok(spec)
+}
+
+/// Turns a spec into a list of processes, sorted by arrival time.
+/// Every population is generated from its own seed, derived from the spec seed
+/// and its name, so adding or removing a population doesn't change the others.
+pub fn generate_workload(spec: &WorkloadSpec) -> Result<Vec<ProcessSpec>, Error> {
+    check_version(spec)?;
+    let mut names: Vec<&str> = spec.populations.iter().map(|x| x.name.as_str()).collect();
+    names.sort();
+    if let Some(duplicate) = names.windows(2).find(|x| x[0] == x[1]) {
+        return Err(Error::validation(format!(
+            "population '{}' is defined more than once",
+            duplicate[0]
+        )));
+    }
+
+    for population in &spec.populations {
+        if let Some((lowest, highest)) = population.priorities {
+            if lowest > highest {
+                return Err(Error::configuration(format!(
+                    "population '{}' has an empty priority range [{}, {}]",
+                    population.name, lowest, highest
+                )));
+            }
+        }
+    }
+
+    let mut queue: Vec<ProcessSpec> = vec![];
+    for population in &spec.populations {
+        let mut rng = StdRng::seed_from_u64(spec.seed ^ hash_name(&population.name));
+        let mut arrivals = population.arrivals.build()?;
+        for i in 0..population.processes_count {
+            let Some(arrival_time) = arrivals.next_arrival(&mut rng) else {
+                break;
+            };
+            let burst_time = population.bursts.sample(&mut rng)?;
+            let priority = population
+                .priorities
+                .map(|(lowest, highest)| rng.random_range(lowest..=highest));
+            let page_references = match &population.page_references {
+                Some(spec) => {
+                    // References come from their own stream, so that their
+                    // count doesn't shift the draws of the following processes
+                    let references = generate_references(
+                        spec.pattern.build()?.as_mut(),
+                        spec.references_count,
+                        &mut StdRng::seed_from_u64(rng.random()),
+                    );
+                    if let Some(addresses_count) = spec.pattern.addresses_count() {
+                        validate_references(&references, addresses_count)?;
+                    }
+                    Some(references)
+                }
+                None => None,
+            };
+            let io_requests = match &population.io_pattern {
+                Some(spec) => Some(spec.generate(burst_time, &mut rng)?),
+                None => None,
+            };
+            queue.push(ProcessSpec {
+                arrival_time,
+                burst_time,
+                priority,
+                name: format!("{}_{}", population.name, i),
+                page_references,
+                job: None,
+                io_requests,
+            });
+        }
+    }
+    queue.sort_by(|a, b| a.arrival_time.total_cmp(&b.arrival_time));
+    validate_processes(&queue)?;
+    Ok(queue)
+}
+
+impl ArrivalSpec {
+    fn build(&self) -> Result<Box<dyn ArrivalProcess>, Error> {
+        Ok(match self {
+            ArrivalSpec::UniformSteps { step_probability } => {
+                Box::new(UniformSteps::new(*step_probability)?)
+            }
+            ArrivalSpec::Poisson { rate } => Box::new(Poisson::new(*rate)?),
+            ArrivalSpec::OnOffBursts {
+                on_rate,
+                off_rate,
+                mean_on_duration,
+                mean_off_duration,
+            } => Box::new(OnOffBursts::new(
+                *on_rate,
+                *off_rate,
+                *mean_on_duration,
+                *mean_off_duration,
+            )?),
+            ArrivalSpec::Periodic { period, jitter } => Box::new(Periodic::new(*period, *jitter)?),
+            ArrivalSpec::Trace { path } => Box::new(ArrivalTrace::read_from(path)?),
+        })
+    }
+}
+
+impl ReferencePatternSpec {
+    /// Pages the pattern is supposed to reference, from 0 on.
+    /// None for sequential scans, which never come back to a page.
+    fn addresses_count(&self) -> Option<u64> {
+        match *self {
+            ReferencePatternSpec::PhasedWorkingSet { pages_count, .. }
+            | ReferencePatternSpec::Zipf { pages_count, .. } => Some(pages_count),
+            ReferencePatternSpec::SequentialScan { .. } => None,
+            ReferencePatternSpec::LoopingScan { start, length } => Some(start + length),
+        }
+    }
+
+    fn build(&self) -> Result<Box<dyn ReferenceGenerator>, Error> {
+        Ok(match *self {
+            ReferencePatternSpec::PhasedWorkingSet {
+                pages_count,
+                working_set_size,
+                phase_length,
+            } => Box::new(PhasedWorkingSet::new(
+                pages_count,
+                working_set_size,
+                phase_length,
+            )?),
+            ReferencePatternSpec::Zipf {
+                pages_count,
+                exponent,
+            } => Box::new(ZipfPopularity::new(pages_count, exponent)?),
+            ReferencePatternSpec::SequentialScan { start, length } => {
+                Box::new(Scan::sequential(start, length))
+            }
+            ReferencePatternSpec::LoopingScan { start, length } => {
+                Box::new(Scan::looping(start, length))
+            }
+        })
+    }
+}
+
+impl TryFrom<Map<String, Value>> for PageReferencesSpec {
+    type Error = String;
+
+    fn try_from(mut fields: Map<String, Value>) -> Result<Self, Self::Error> {
+        let references_count = fields
+            .remove("references_count")
+            .ok_or("missing field `references_count`")?;
+        Ok(PageReferencesSpec {
+            references_count: serde_json::from_value(references_count)
+                .map_err(|error| error.to_string())?,
+            pattern: serde_json::from_value(Value::Object(fields))
+                .map_err(|error| error.to_string())?,
+        })
+    }
+}
+
+impl IoPatternSpec {
+    /// Requests made until the process used up `burst_time` of CPU time.
+    /// Fails if an interval doesn't move the CPU time forward.
+    fn generate(&self, burst_time: f32, rng: &mut StdRng) -> Result<Vec<IoRequest>, Error> {
+        let mut requests: Vec<IoRequest> = vec![];
+        let mut cpu_time = 0.0;
+        loop {
+            let next_request = cpu_time + self.interval.sample(rng)?;
+            if next_request <= cpu_time {
+                return Err(Error::configuration(format!(
+                    "I/O interval {:?} has to be positive",
+                    self.interval
+                )));
+            }
+            // Not finite bursts are rejected along with the queue
+            if next_request >= burst_time || !burst_time.is_finite() {
+                return Ok(requests);
+            }
+            cpu_time = next_request;
+            requests.push(IoRequest {
+                cpu_time,
+                duration: self.duration.sample(rng)?,
+            });
+        }
+    }
+}
+
+fn check_version(spec: &WorkloadSpec) -> Result<(), Error> {
+    if spec.version != WORKLOAD_SPEC_VERSION {
+        return Err(Error::configuration(format!(
+            "unsupported workload spec version {}, expected {}",
+            spec.version, WORKLOAD_SPEC_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Configuration errors don't carry a path, the spec is named in the message instead
+fn prefix_path(error: Error, path: &str) -> Error {
+    match error {
+        Error::Configuration(message) => Error::configuration(format!("{}: {}", path, message)),
+        error => error,
+    }
+}
+
+/// Same as `read_json_from`, for TOML files
+fn read_toml_from<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
+    let read = || -> Result<T, Error> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|error| {
+            let line = error
+                .span()
+                .map(|span| text[..span.start].matches('\n').count() + 1);
+            Error::parse(line, error.message())
+        })
+    };
+    read().map_err(|error| error.in_file(path))
+}
+
+/// FNV-1a, stable across runs and platforms unlike the standard library hasher
+fn hash_name(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod workload_tests {
+    use super::*;
+    use crate::validation::{check_processes, check_references};
+
+    const SPEC: &str = r#"{
+        "version": 1,
+        "seed": 7,
+        "populations": [
+            {
+                "name": "Interactive",
+                "processes_count": 20,
+                "arrivals": {"model": "poisson", "rate": 0.5},
+                "bursts": {"distribution": "exponential", "mean": 2.0},
+                "priorities": [0, 3],
+                "page_references": {
+                    "references_count": 50,
+                    "pattern": "zipf",
+                    "pages_count": 16,
+                    "exponent": 1.0
+                }
+            },
+            {
+                "name": "Batch",
+                "processes_count": 5,
+                "arrivals": {"model": "periodic", "period": 10.0, "jitter": 0.0},
+                "bursts": {"distribution": "pareto", "scale": 20.0, "shape": 1.5}
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_generate_workload() {
+        let spec: WorkloadSpec = serde_json::from_str(SPEC).unwrap();
+        let queue = generate_workload(&spec).unwrap();
+        assert!(queue.len() == 25);
+        assert!(queue
+            .windows(2)
+            .all(|x| x[0].arrival_time <= x[1].arrival_time));
+
+        let interactive: Vec<&ProcessSpec> = queue
+            .iter()
+            .filter(|x| x.name.starts_with("Interactive_"))
+            .collect();
+        assert!(interactive.len() == 20);
+        assert!(interactive.iter().all(|x| x.priority.unwrap() <= 3));
+        assert!(interactive
+            .iter()
+            .all(|x| x.page_references.as_ref().unwrap().len() == 50));
+        assert!(interactive.iter().all(|x| check_references(
+            x.page_references.as_ref().unwrap(),
+            16
+        )
+        .is_empty()));
+        assert!(check_processes(&queue).is_empty());
+
+        let batch: Vec<&ProcessSpec> = queue
+            .iter()
+            .filter(|x| x.name.starts_with("Batch_"))
+            .collect();
+        assert!(batch
+            .iter()
+            .all(|x| x.priority.is_none() && x.burst_time >= 20.0));
+        assert!(batch[4].arrival_time == 40.0);
+    }
+
+    #[test]
+    fn test_populations_are_independent() {
+        let spec: WorkloadSpec = serde_json::from_str(SPEC).unwrap();
+        let both = generate_workload(&spec).unwrap();
+        let mut batch_only = spec.clone();
+        batch_only.populations.remove(0);
+        let batch_only = generate_workload(&batch_only).unwrap();
+        let batch: Vec<f32> = both
+            .iter()
+            .filter(|x| x.name.starts_with("Batch_"))
+            .map(|x| x.burst_time)
+            .collect();
+        assert!(
+            batch
+                == batch_only
+                    .iter()
+                    .map(|x| x.burst_time)
+                    .collect::<Vec<f32>>()
+        );
+        // Same spec, same workload
+        let again = generate_workload(&spec).unwrap();
+        assert!(serde_json::to_string(&again).unwrap() == serde_json::to_string(&both).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_specs() {
+        let mut spec: WorkloadSpec = serde_json::from_str(SPEC).unwrap();
+        spec.version = 2;
+        assert!(generate_workload(&spec).is_err());
+        spec.version = 1;
+        spec.populations[1].name = "Interactive".to_owned();
+        let error = generate_workload(&spec).err().unwrap();
+        assert!(error.to_string().contains("more than once"));
+        spec.populations[1].name = "Batch".to_owned();
+        spec.populations[0].priorities = Some((3, 0));
+        let error = generate_workload(&spec).err().unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+        assert!(
+            serde_json::from_str::<WorkloadSpec>(r#"{"version": 1, "seed": 1, "typo": 1}"#)
+                .is_err()
+        );
+
+        // Misspelled optional keys are rejected at every level
+        for key in [
+            r#""mean": 2.0"#,
+            r#""references_count": 50"#,
+            r#""exponent": 1.0"#,
+        ] {
+            let misspelled = SPEC.replacen(key, &format!(r#"{}, "pirorities": [0, 3]"#, key), 1);
+            assert!(serde_json::from_str::<WorkloadSpec>(&misspelled).is_err());
+        }
+    }
+
+    #[test]
+    fn test_io_pattern() {
+        let mut spec: WorkloadSpec = serde_json::from_str(SPEC).unwrap();
+        let without_io = generate_workload(&spec).unwrap();
+        assert!(without_io.iter().all(|x| x.io_requests.is_none()));
+        spec.populations[1].io_pattern = serde_json::from_str(
+            r#"{
+                "interval": {"distribution": "exponential", "mean": 4.0},
+                "duration": {"distribution": "exponential", "mean": 10.0}
+            }"#,
+        )
+        .unwrap();
+        let queue = generate_workload(&spec).unwrap();
+        let batch: Vec<&ProcessSpec> = queue
+            .iter()
+            .filter(|x| x.name.starts_with("Batch_"))
+            .collect();
+        for process in &batch {
+            let requests = process.io_requests.as_ref().unwrap();
+            assert!(requests.windows(2).all(|x| x[0].cpu_time < x[1].cpu_time));
+            assert!(requests
+                .iter()
+                .all(|x| x.cpu_time < process.burst_time && x.duration >= 0.0));
+        }
+        assert!(batch
+            .iter()
+            .any(|x| x.io_requests.as_ref().unwrap().len() > 1));
+        // Other populations draw the same numbers as before
+        let interactive = |queue: &[ProcessSpec]| -> String {
+            let processes: Vec<&ProcessSpec> = queue
+                .iter()
+                .filter(|x| x.name.starts_with("Interactive_"))
+                .collect();
+            serde_json::to_string(&processes).unwrap()
+        };
+        assert!(interactive(&queue) == interactive(&without_io));
+
+        spec.populations[1].io_pattern = Some(IoPatternSpec {
+            interval: BurstDistribution::Exponential { mean: 0.0 },
+            duration: BurstDistribution::Exponential { mean: 1.0 },
+        });
+        let error = generate_workload(&spec).err().unwrap();
+        assert!(matches!(error, Error::Configuration(_)));
+    }
+
+    #[test]
+    fn test_read_toml_spec() {
+        let path = std::env::temp_dir().join("scheduling_project_workload_spec.toml");
+        let path = path.to_str().unwrap();
+        fs::write(
+            path,
+            r#"
+version = 1
+seed = 7
+
+[[populations]]
+name = "Interactive"
+processes_count = 20
+arrivals = { model = "poisson", rate = 0.5 }
+bursts = { distribution = "exponential", mean = 2.0 }
+priorities = [0, 3]
+page_references = { references_count = 50, pattern = "zipf", pages_count = 16, exponent = 1.0 }
+
+[[populations]]
+name = "Batch"
+processes_count = 5
+arrivals = { model = "periodic", period = 10.0, jitter = 0.0 }
+bursts = { distribution = "pareto", scale = 20.0, shape = 1.5 }
+"#,
+        )
+        .unwrap();
+        let spec = read_workload_spec_from(path).unwrap();
+        assert!(spec == serde_json::from_str::<WorkloadSpec>(SPEC).unwrap());
+
+        fs::write(path, "version = 1\nseed = 7\ntypo = 1\n").unwrap();
+        let error = read_workload_spec_from(path).err().unwrap();
+        assert!(matches!(error, Error::Parse { line: Some(3), .. }));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_spec_with_includes() {
+        let spec = read_workload_spec_from("test_data/workload_spec.json").unwrap();
+        let names: Vec<&str> = spec.populations.iter().map(|x| x.name.as_str()).collect();
+        assert!(names == vec!["Interactive", "Batch"]);
+        assert!(spec.includes.is_empty());
+        let queue = generate_workload(&spec).unwrap();
+        assert!(queue.len() == 40);
+    }
+
+    #[test]
+    fn test_include_cycles() {
+        let directory = std::env::temp_dir().join("scheduling_project_include_cycle");
+        fs::create_dir_all(&directory).unwrap();
+        let spec = |include: &str| {
+            format!(
+                r#"{{"version": 1, "seed": 1, "includes": ["{}"]}}"#,
+                include
+            )
+        };
+        fs::write(directory.join("a.json"), spec("b.json")).unwrap();
+        fs::write(directory.join("b.json"), spec("a.json")).unwrap();
+        fs::write(directory.join("self.json"), spec("self.json")).unwrap();
+        for file in ["a.json", "self.json"] {
+            let path = directory.join(file);
+            let error = read_workload_spec_from(path.to_str().unwrap())
+                .err()
+                .unwrap();
+            assert!(error.to_string().contains("included more than once"));
+        }
+        fs::remove_dir_all(directory).unwrap();
+    }
+}